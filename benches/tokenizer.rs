@@ -0,0 +1,30 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use json_formatter::tokenizer::tokenize;
+use std::hint::black_box;
+
+fn document(name: &str) -> String {
+    let records: Vec<String> = (0..2000)
+        .map(|index| {
+            format!(
+                r#"{{"id": {}, "name": "{} {}", "active": true, "scores": [1.5, 2.25, -3e2], "tags": null}}"#,
+                index, name, index
+            )
+        })
+        .collect();
+    format!("[{}]", records.join(",\n"))
+}
+
+fn bench_tokenize(c: &mut Criterion) {
+    // The same document, except that a single non-ASCII character forces the
+    // UTF-8 decoding path for the whole input.
+    let ascii = document("record");
+    let unicode = document("récord");
+
+    c.bench_function("tokenize ascii", |b| b.iter(|| tokenize(black_box(&ascii))));
+    c.bench_function("tokenize unicode", |b| {
+        b.iter(|| tokenize(black_box(&unicode)))
+    });
+}
+
+criterion_group!(benches, bench_tokenize);
+criterion_main!(benches);
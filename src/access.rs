@@ -0,0 +1,182 @@
+use crate::parser::{JsonNumber, JsonValue};
+use std::fmt;
+
+/// The error returned by the typed getters on `JsonValue`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    /// The getter was called on something other than an object.
+    NotAnObject {
+        found: &'static str,
+    },
+    MissingKey {
+        key: String,
+    },
+    WrongType {
+        key: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeError::NotAnObject { found } => write!(f, "Expected an object, found {}", found),
+            TypeError::MissingKey { key } => write!(f, "Missing key: '{}'", key),
+            TypeError::WrongType {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} for key '{}', found {}",
+                expected, key, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+impl JsonValue {
+    /// Looks up `key` in an object and returns it if it is a string.
+    pub fn get_str(&self, key: &str) -> Result<&str, TypeError> {
+        match self.get_key(key)? {
+            JsonValue::String(s) => Ok(s),
+            value => Err(wrong_type(key, "a string", value)),
+        }
+    }
+
+    /// Looks up `key` in an object and returns it if it is an integer.
+    pub fn get_i64(&self, key: &str) -> Result<i64, TypeError> {
+        match self.get_key(key)? {
+            JsonValue::Number(JsonNumber::Integer(n)) => Ok(*n),
+            value => Err(wrong_type(key, "an integer", value)),
+        }
+    }
+
+    /// Looks up `key` in an object and returns it if it is a number, integer or not.
+    pub fn get_f64(&self, key: &str) -> Result<f64, TypeError> {
+        match self.get_key(key)? {
+            JsonValue::Number(n) => Ok(n.as_f64()),
+            value => Err(wrong_type(key, "a number", value)),
+        }
+    }
+
+    /// Looks up `key` in an object and returns it if it is a boolean.
+    pub fn get_bool(&self, key: &str) -> Result<bool, TypeError> {
+        match self.get_key(key)? {
+            JsonValue::Bool(b) => Ok(*b),
+            value => Err(wrong_type(key, "a boolean", value)),
+        }
+    }
+
+    /// Looks up `key` in an object and returns it if it is an array.
+    pub fn get_array(&self, key: &str) -> Result<&[JsonValue], TypeError> {
+        match self.get_key(key)? {
+            JsonValue::Array(values) => Ok(values),
+            value => Err(wrong_type(key, "an array", value)),
+        }
+    }
+
+    /// If the object repeats `key`, the last value is used.
+    fn get_key(&self, key: &str) -> Result<&JsonValue, TypeError> {
+        let JsonValue::Object(entries) = self else {
+            return Err(TypeError::NotAnObject {
+                found: type_name(self),
+            });
+        };
+        entries
+            .iter()
+            .rev()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+            .ok_or_else(|| TypeError::MissingKey {
+                key: key.to_string(),
+            })
+    }
+}
+
+fn wrong_type(key: &str, expected: &'static str, found: &JsonValue) -> TypeError {
+    TypeError::WrongType {
+        key: key.to_string(),
+        expected,
+        found: type_name(found),
+    }
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "a boolean",
+        JsonValue::Number(JsonNumber::Integer(_)) => "an integer",
+        JsonValue::Number(JsonNumber::Float(_)) => "a float",
+        JsonValue::String(_) => "a string",
+        JsonValue::Array(_) => "an array",
+        JsonValue::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> JsonValue {
+        crate::parse(r#"{"name": "Alice", "age": 30, "score": 9.5, "admin": false, "tags": ["a"]}"#)
+            .unwrap()
+    }
+
+    #[test]
+    fn typed_getters() {
+        let user = user();
+        assert_eq!(user.get_str("name"), Ok("Alice"));
+        assert_eq!(user.get_i64("age"), Ok(30));
+        assert_eq!(user.get_f64("age"), Ok(30.0));
+        assert_eq!(user.get_f64("score"), Ok(9.5));
+        assert_eq!(user.get_bool("admin"), Ok(false));
+        assert_eq!(
+            user.get_array("tags"),
+            Ok(&[JsonValue::String("a".to_string())][..])
+        );
+    }
+
+    #[test]
+    fn typed_getters_wrong_type() {
+        let user = user();
+        let error = user.get_i64("score").unwrap_err();
+        assert_eq!(
+            error,
+            TypeError::WrongType {
+                key: "score".to_string(),
+                expected: "an integer",
+                found: "a float",
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Expected an integer for key 'score', found a float"
+        );
+        assert_eq!(
+            user.get_str("tags").unwrap_err().to_string(),
+            "Expected a string for key 'tags', found an array"
+        );
+        assert_eq!(
+            JsonValue::Null.get_bool("admin").unwrap_err().to_string(),
+            "Expected an object, found null"
+        );
+    }
+
+    #[test]
+    fn typed_getters_missing_key() {
+        assert_eq!(
+            user().get_str("email"),
+            Err(TypeError::MissingKey {
+                key: "email".to_string()
+            })
+        );
+        assert_eq!(
+            user().get_bool("email").unwrap_err().to_string(),
+            "Missing key: 'email'"
+        );
+    }
+}
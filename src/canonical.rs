@@ -0,0 +1,96 @@
+use crate::formatter::{format_with_options, FormatOptions, KeyOrder};
+use crate::parser::JsonValue;
+use std::collections::HashSet;
+
+/// Writes `value` in a canonical form, so that documents differing only in
+/// whitespace, key order, repeated keys or number notation are written alike.
+///
+/// The output is compact, with object keys sorted by code point, the last value
+/// of a repeated key kept, and numbers written as `JSON.stringify` writes them,
+/// so `1.0` and `1` are both written as `1`.
+pub fn to_canonical_string(value: &JsonValue) -> String {
+    let options = FormatOptions {
+        compact: true,
+        sort_keys: Some(KeyOrder::CodePoint),
+        ecmascript_numbers: true,
+        ..FormatOptions::default()
+    };
+    format_with_options(&without_repeated_keys(value), &options)
+}
+
+/// Returns the SHA-256 hash of the canonical form of `value`, for
+/// content-addressable storage.
+#[cfg(feature = "sha2")]
+pub fn content_hash(value: &JsonValue) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(to_canonical_string(value).as_bytes()).into()
+}
+
+/// Returns `content_hash` as 64 lowercase hex digits.
+#[cfg(feature = "sha2")]
+pub fn content_hash_hex(value: &JsonValue) -> String {
+    content_hash(value)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Returns a copy of `value` where each object keeps only the last entry of a
+/// repeated key.
+fn without_repeated_keys(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Array(values) => {
+            JsonValue::Array(values.iter().map(without_repeated_keys).collect())
+        }
+        JsonValue::Object(entries) => {
+            let mut keys = HashSet::new();
+            let mut kept: Vec<(String, JsonValue)> = entries
+                .iter()
+                .rev()
+                .filter(|(key, _)| keys.insert(key.as_str()))
+                .map(|(key, value)| (key.clone(), without_repeated_keys(value)))
+                .collect();
+            kept.reverse();
+            JsonValue::Object(kept)
+        }
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_string_ignores_layout() {
+        let a = crate::parse(r#"{"b": null, "a": [1.0, "x"], "b": 2}"#).unwrap();
+        let b = crate::parse("{\n  \"a\": [1, \"x\"],\n  \"b\": 2e0\n}").unwrap();
+        assert_eq!(to_canonical_string(&a), r#"{"a":[1,"x"],"b":2}"#);
+        assert_eq!(to_canonical_string(&a), to_canonical_string(&b));
+    }
+
+    #[test]
+    fn canonical_string_sorts_deeply_nested_keys() {
+        let nested = |inner: &str| format!("{}{}{}", "[".repeat(200), inner, "]".repeat(200));
+        let a = crate::parse(&nested(r#"{"b": 1, "a": 2}"#)).unwrap();
+        let b = crate::parse(&nested(r#"{"a": 2, "b": 1}"#)).unwrap();
+        assert_eq!(to_canonical_string(&a), nested(r#"{"a":2,"b":1}"#));
+        assert_eq!(to_canonical_string(&a), to_canonical_string(&b));
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn content_hash_of_equal_documents() {
+        let a = crate::parse(r#"{"b": null, "a": [1.0, "x"]}"#).unwrap();
+        let b = crate::parse(r#"{"a": [1, "x"], "b": null}"#).unwrap();
+        assert_eq!(content_hash(&a), content_hash(&b));
+        assert_eq!(
+            content_hash_hex(&a),
+            "854ef06dc57f5dfed10206344ab2d02e0b6c84b0e19436703a5afd0f1f9f2687"
+        );
+
+        let c = crate::parse(r#"{"a": [1, "y"], "b": null}"#).unwrap();
+        assert_ne!(content_hash(&a), content_hash(&c));
+    }
+}
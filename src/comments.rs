@@ -0,0 +1,362 @@
+use crate::error::Error;
+use crate::formatter::{self, FormatOptions};
+use crate::parser::{self, JsonValue};
+use crate::query::push_segment;
+use crate::spanned::{self, SpannedKind, SpannedValue};
+use crate::tokenizer::{tokenize_located, Comment, CommentKind, TokenizeOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommentPosition {
+    /// On the lines before the value (before the key, for object entries).
+    Leading,
+    /// On the same line, after the value and its comma.
+    Trailing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttachedComment {
+    /// The JSON Pointer of the value the comment belongs to.
+    pub path: String,
+    pub position: CommentPosition,
+    pub comment: Comment,
+}
+
+/// A value parsed from JSON with comments, which remembers where its comments were.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentedValue {
+    pub value: JsonValue,
+    pub comments: Vec<AttachedComment>,
+}
+
+/// Parses `content`, allowing comments and attaching each one to the nearest value.
+///
+/// A comment that follows a value on the same line, with nothing but a comma in
+/// between, trails that value. Any other comment leads the next value, or trails
+/// the previous one if no value follows.
+pub fn parse_with_comments(content: &str) -> Result<CommentedValue, Error> {
+    let options = TokenizeOptions {
+        allow_comments: true,
+        ..TokenizeOptions::default()
+    };
+    let (tokens, comments) = tokenize_located(content, &options)?;
+    let value = spanned::parse_all(content, &tokens)?;
+
+    let mut anchors = Vec::new();
+    collect_anchors(&value, &mut String::new(), value.span.start, &mut anchors);
+
+    Ok(CommentedValue {
+        value: value.to_value(),
+        comments: comments
+            .into_iter()
+            .map(|comment| attach(content, &anchors, comment))
+            .collect(),
+    })
+}
+
+/// Parses `content`, allowing comments, and returns every comment in document
+/// order alongside the value, without attaching them to anything.
+pub fn parse_with_comment_list(content: &str) -> Result<(JsonValue, Vec<Comment>), Error> {
+    let options = TokenizeOptions {
+        allow_comments: true,
+        ..TokenizeOptions::default()
+    };
+    let (spanned, comments) = tokenize_located(content, &options)?;
+    let tokens: Vec<_> = spanned
+        .iter()
+        .map(|spanned| spanned.token.clone())
+        .collect();
+    let value = parser::parse_tokens(&tokens)
+        .map_err(|(error, position)| spanned::locate(content, &spanned, position, error))?;
+    Ok((value, comments))
+}
+
+/// The source range a comment can attach to, from the key (if any) to the end of the value.
+struct Anchor {
+    path: String,
+    start: usize,
+    end: usize,
+}
+
+fn collect_anchors(
+    value: &SpannedValue,
+    path: &mut String,
+    start: usize,
+    anchors: &mut Vec<Anchor>,
+) {
+    anchors.push(Anchor {
+        path: path.clone(),
+        start,
+        end: value.span.end,
+    });
+
+    match &value.kind {
+        SpannedKind::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                collect_anchors(value, path, value.span.start, anchors);
+                path.truncate(len);
+            }
+        }
+        SpannedKind::Object(entries) => {
+            for entry in entries {
+                let len = path.len();
+                push_segment(path, &entry.key);
+                collect_anchors(&entry.value, path, entry.key_span.start, anchors);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn attach(content: &str, anchors: &[Anchor], comment: Comment) -> AttachedComment {
+    let previous = anchors
+        .iter()
+        .filter(|anchor| anchor.end <= comment.span.start)
+        .max_by_key(|anchor| anchor.end);
+    let next = anchors
+        .iter()
+        .filter(|anchor| anchor.start >= comment.span.end)
+        .min_by_key(|anchor| anchor.start);
+
+    let (anchor, position) = match (previous, next) {
+        (Some(previous), _)
+            if content[previous.end..comment.span.start]
+                .chars()
+                .all(|char| matches!(char, ',' | ' ' | '\t')) =>
+        {
+            (previous, CommentPosition::Trailing)
+        }
+        (_, Some(next)) => (next, CommentPosition::Leading),
+        (Some(previous), None) => (previous, CommentPosition::Trailing),
+        (None, None) => (&anchors[0], CommentPosition::Leading),
+    };
+
+    AttachedComment {
+        path: anchor.path.clone(),
+        position,
+        comment,
+    }
+}
+
+/// Formats a value parsed by `parse_with_comments`, writing its comments back.
+///
+/// The output is JSON with comments (JSONC), not standard JSON. Comments need the
+/// multi-line layout, so only the `indent`, `emit_bom` and string escaping options
+/// apply.
+pub fn format_with_comments(commented: &CommentedValue, options: &FormatOptions) -> String {
+    let mut out = String::new();
+    let mut path = String::new();
+
+    if options.emit_bom {
+        out.push('\u{FEFF}');
+    }
+    format_leading(&mut out, commented, &path, 0, options);
+    format_value(&mut out, &commented.value, &mut path, 0, commented, options);
+    format_trailing(&mut out, commented, &path);
+
+    out
+}
+
+fn format_value(
+    out: &mut String,
+    value: &JsonValue,
+    path: &mut String,
+    depth: usize,
+    commented: &CommentedValue,
+    options: &FormatOptions,
+) {
+    match value {
+        JsonValue::Array(values) if !values.is_empty() => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                out.push('\n');
+                format_leading(out, commented, path, depth + 1, options);
+                out.push_str(&options.indent.repeat(depth + 1));
+                format_value(out, value, path, depth + 1, commented, options);
+                if index + 1 < values.len() {
+                    out.push(',');
+                }
+                format_trailing(out, commented, path);
+                path.truncate(len);
+            }
+            out.push('\n');
+            out.push_str(&options.indent.repeat(depth));
+            out.push(']');
+        }
+        JsonValue::Object(entries) if !entries.is_empty() => {
+            out.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, key);
+                out.push('\n');
+                format_leading(out, commented, path, depth + 1, options);
+                out.push_str(&options.indent.repeat(depth + 1));
+                formatter::format_string(out, key, options).unwrap();
+                out.push_str(": ");
+                format_value(out, value, path, depth + 1, commented, options);
+                if index + 1 < entries.len() {
+                    out.push(',');
+                }
+                format_trailing(out, commented, path);
+                path.truncate(len);
+            }
+            out.push('\n');
+            out.push_str(&options.indent.repeat(depth));
+            out.push('}');
+        }
+        _ => out.push_str(&formatter::format_fragment(value, options)),
+    }
+}
+
+fn format_leading(
+    out: &mut String,
+    commented: &CommentedValue,
+    path: &str,
+    depth: usize,
+    options: &FormatOptions,
+) {
+    for attached in comments_at(commented, path, CommentPosition::Leading) {
+        out.push_str(&options.indent.repeat(depth));
+        format_comment(out, &attached.comment);
+        out.push('\n');
+    }
+}
+
+fn format_trailing(out: &mut String, commented: &CommentedValue, path: &str) {
+    for attached in comments_at(commented, path, CommentPosition::Trailing) {
+        out.push(' ');
+        format_comment(out, &attached.comment);
+    }
+}
+
+fn comments_at<'a>(
+    commented: &'a CommentedValue,
+    path: &'a str,
+    position: CommentPosition,
+) -> impl Iterator<Item = &'a AttachedComment> {
+    commented
+        .comments
+        .iter()
+        .filter(move |attached| attached.path == path && attached.position == position)
+}
+
+fn format_comment(out: &mut String, comment: &Comment) {
+    match comment.kind {
+        CommentKind::Line => {
+            out.push_str("// ");
+            out.push_str(&comment.text);
+        }
+        CommentKind::Block => {
+            out.push_str("/* ");
+            out.push_str(&comment.text);
+            out.push_str(" */");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_round_trip() {
+        let content = r#"// leading on the document
+{
+  // about a
+  "a": 1, // trailing a
+  "b": [
+    true /* after true */
+  ]
+} // end"#;
+        let commented = parse_with_comments(content).unwrap();
+        assert_eq!(
+            commented.value,
+            crate::parse(r#"{"a": 1, "b": [true]}"#).unwrap()
+        );
+        assert_eq!(
+            format_with_comments(&commented, &FormatOptions::default()),
+            content
+        );
+    }
+
+    #[test]
+    fn comments_are_attached_to_values() {
+        let content = "{\n  // about a\n  \"a\": 1, // trailing a\n  \"b\": 2\n}";
+        let commented = parse_with_comments(content).unwrap();
+        let attached: Vec<(&str, CommentPosition, &str)> = commented
+            .comments
+            .iter()
+            .map(|attached| {
+                (
+                    attached.path.as_str(),
+                    attached.position,
+                    attached.comment.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            attached,
+            vec![
+                ("/a", CommentPosition::Leading, "about a"),
+                ("/a", CommentPosition::Trailing, "trailing a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_are_normalized_when_formatting() {
+        let content = "{\"a\":1,/* on a */\n\"b\":[]}";
+        let commented = parse_with_comments(content).unwrap();
+        assert_eq!(
+            format_with_comments(&commented, &FormatOptions::default()),
+            "{\n  \"a\": 1, /* on a */\n  \"b\": []\n}"
+        );
+    }
+
+    #[test]
+    fn comments_are_formatted_after_one_byte_order_mark() {
+        let content = "// top\n{\"a\": 1, \"b\": [true, null]}";
+        let options = FormatOptions {
+            emit_bom: true,
+            ..FormatOptions::default()
+        };
+        let formatted = format_with_comments(&parse_with_comments(content).unwrap(), &options);
+        assert_eq!(
+            formatted,
+            "\u{FEFF}// top\n{\n  \"a\": 1,\n  \"b\": [\n    true,\n    null\n  ]\n}"
+        );
+
+        // The byte order mark is skipped when the output is read back.
+        let reformatted = format_with_comments(&parse_with_comments(&formatted).unwrap(), &options);
+        assert_eq!(reformatted, formatted);
+    }
+
+    #[test]
+    fn comment_list_returns_every_comment() {
+        let content = "// first\n{\n  \"a\": /* inline */ 1 // last\n}";
+        let (value, comments) = parse_with_comment_list(content).unwrap();
+        assert_eq!(value, crate::parse(r#"{"a": 1}"#).unwrap());
+        let listed: Vec<(&str, CommentKind, &str)> = comments
+            .iter()
+            .map(|comment| {
+                (
+                    comment.text.as_str(),
+                    comment.kind,
+                    &content[comment.span.start..comment.span.end],
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("first", CommentKind::Line, "// first"),
+                ("inline", CommentKind::Block, "/* inline */"),
+                ("last", CommentKind::Line, "// last"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use crate::parser::JsonValue;
+#[cfg(feature = "toml")]
+use crate::query::push_segment;
+#[cfg(feature = "toml")]
+use std::fmt;
+
+/// Writes `value` as a YAML document in block style, with two spaces per level.
+///
+/// Strings are written plain when YAML would read them back as the same string,
+/// and double-quoted with JSON escapes otherwise.
+#[cfg(feature = "yaml")]
+pub fn to_yaml(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_yaml(&mut out, value, 0);
+    out.push('\n');
+    out
+}
+
+/// Writes `value` where the cursor is, indenting any further lines by `indent`.
+#[cfg(feature = "yaml")]
+fn write_yaml(out: &mut String, value: &JsonValue, indent: usize) {
+    match value {
+        JsonValue::Object(entries) if !entries.is_empty() => {
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    yaml_line_break(out, indent);
+                }
+                write_yaml_string(out, key);
+                out.push(':');
+                if is_yaml_block(value) {
+                    yaml_line_break(out, indent + 2);
+                    write_yaml(out, value, indent + 2);
+                } else {
+                    out.push(' ');
+                    write_yaml(out, value, indent);
+                }
+            }
+        }
+        JsonValue::Array(values) if !values.is_empty() => {
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    yaml_line_break(out, indent);
+                }
+                out.push_str("- ");
+                write_yaml(out, value, indent + 2);
+            }
+        }
+        JsonValue::Object(_) => out.push_str("{}"),
+        JsonValue::Array(_) => out.push_str("[]"),
+        JsonValue::String(string) => write_yaml_string(out, string),
+        _ => out.push_str(&crate::formatter::format(value)),
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn is_yaml_block(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(entries) => !entries.is_empty(),
+        JsonValue::Array(values) => !values.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_line_break(out: &mut String, indent: usize) {
+    out.push('\n');
+    out.push_str(&" ".repeat(indent));
+}
+
+#[cfg(feature = "yaml")]
+fn write_yaml_string(out: &mut String, string: &str) {
+    // Anything YAML could read as another type, such as `no` or `1.5`, is quoted.
+    let reserved = matches!(
+        string.to_ascii_lowercase().as_str(),
+        "true" | "false" | "null" | "yes" | "no" | "on" | "off" | "y" | "n"
+    );
+    let plain = string.starts_with(|char: char| char.is_ascii_alphabetic() || char == '_')
+        && string
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || " _-./".contains(char))
+        && !string.ends_with(' ')
+        && !reserved;
+    if plain {
+        out.push_str(string);
+    } else {
+        out.push_str(&crate::formatter::format(&JsonValue::String(
+            string.to_string(),
+        )));
+    }
+}
+
+/// The error returned by `to_toml` for values TOML cannot represent.
+#[cfg(feature = "toml")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertError {
+    /// A TOML document is a table, so only an object can be converted.
+    NotATable,
+    /// TOML has no null. Holds the JSON Pointer of the null value.
+    Null(String),
+}
+
+#[cfg(feature = "toml")]
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConvertError::NotATable => write!(f, "Only an object can be converted to TOML"),
+            ConvertError::Null(path) => write!(f, "TOML cannot represent the null at '{}'", path),
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl std::error::Error for ConvertError {}
+
+/// Writes `value`, which must be an object, as a TOML document.
+///
+/// Nested objects become `[table]` sections and arrays of objects become
+/// `[[array]]` sections. Objects inside other arrays are written as inline
+/// tables. Arrays mixing types are written as they are, which TOML 1.0 allows.
+#[cfg(feature = "toml")]
+pub fn to_toml(value: &JsonValue) -> Result<String, ConvertError> {
+    let JsonValue::Object(entries) = value else {
+        return Err(ConvertError::NotATable);
+    };
+    let mut out = String::new();
+    write_toml_table(&mut out, entries, "", &mut String::new())?;
+    Ok(out)
+}
+
+/// Writes the entries of the table named `header`: first its keys with inline
+/// values, then its sub-tables, as TOML requires.
+#[cfg(feature = "toml")]
+fn write_toml_table(
+    out: &mut String,
+    entries: &[(String, JsonValue)],
+    header: &str,
+    path: &mut String,
+) -> Result<(), ConvertError> {
+    for (key, value) in entries {
+        if is_toml_table(value) || is_toml_table_array(value) {
+            continue;
+        }
+        let len = path.len();
+        push_segment(path, key);
+        write_toml_key(out, key);
+        out.push_str(" = ");
+        write_toml_value(out, value, path)?;
+        out.push('\n');
+        path.truncate(len);
+    }
+
+    for (key, value) in entries {
+        let mut name = header.to_string();
+        if !name.is_empty() {
+            name.push('.');
+        }
+        write_toml_key(&mut name, key);
+
+        let len = path.len();
+        push_segment(path, key);
+        match value {
+            JsonValue::Object(entries) => {
+                toml_section_break(out);
+                out.push_str(&format!("[{}]\n", name));
+                write_toml_table(out, entries, &name, path)?;
+            }
+            JsonValue::Array(values) if is_toml_table_array(value) => {
+                for (index, value) in values.iter().enumerate() {
+                    let JsonValue::Object(entries) = value else {
+                        unreachable!();
+                    };
+                    let len = path.len();
+                    push_segment(path, &index.to_string());
+                    toml_section_break(out);
+                    out.push_str(&format!("[[{}]]\n", name));
+                    write_toml_table(out, entries, &name, path)?;
+                    path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+        path.truncate(len);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "toml")]
+fn is_toml_table(value: &JsonValue) -> bool {
+    matches!(value, JsonValue::Object(_))
+}
+
+/// Whether `value` is a non-empty array of objects, written as `[[array]]` sections.
+#[cfg(feature = "toml")]
+fn is_toml_table_array(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Array(values) => !values.is_empty() && values.iter().all(is_toml_table),
+        _ => false,
+    }
+}
+
+#[cfg(feature = "toml")]
+fn toml_section_break(out: &mut String) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+}
+
+#[cfg(feature = "toml")]
+fn write_toml_value(
+    out: &mut String,
+    value: &JsonValue,
+    path: &mut String,
+) -> Result<(), ConvertError> {
+    match value {
+        JsonValue::Null => return Err(ConvertError::Null(path.clone())),
+        JsonValue::Bool(_) | JsonValue::Number(_) => out.push_str(&crate::formatter::format(value)),
+        JsonValue::String(string) => write_toml_string(out, string),
+        JsonValue::Array(values) => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                write_toml_value(out, value, path)?;
+                path.truncate(len);
+            }
+            out.push(']');
+        }
+        JsonValue::Object(entries) => {
+            out.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                out.push_str(if index > 0 { ", " } else { " " });
+                let len = path.len();
+                push_segment(path, key);
+                write_toml_key(out, key);
+                out.push_str(" = ");
+                write_toml_value(out, value, path)?;
+                path.truncate(len);
+            }
+            out.push_str(if entries.is_empty() { "}" } else { " }" });
+        }
+    }
+    Ok(())
+}
+
+/// Writes `key` bare if TOML allows it, and as a basic string otherwise.
+#[cfg(feature = "toml")]
+fn write_toml_key(out: &mut String, key: &str) {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-');
+    if bare {
+        out.push_str(key);
+    } else {
+        write_toml_string(out, key);
+    }
+}
+
+#[cfg(feature = "toml")]
+fn write_toml_string(out: &mut String, string: &str) {
+    out.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{0}'..='\u{1f}' | '\u{7f}' => out.push_str(&format!("\\u{:04X}", char as u32)),
+            _ => out.push(char),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn to_yaml_object() {
+        let value = crate::parse(
+            r#"{"name": "app", "version": "1.0", "debug": false, "ports": [80, 443],
+                "db": {"host": "localhost", "options": {}}, "users": [{"id": 1, "admin": null}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_yaml(&value),
+            r#"name: app
+version: "1.0"
+debug: false
+ports:
+  - 80
+  - 443
+db:
+  host: localhost
+  options: {}
+users:
+  - id: 1
+    admin: null
+"#
+        );
+        assert_eq!(to_yaml(&JsonValue::String("no".to_string())), "\"no\"\n");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn to_toml_object() {
+        let value = crate::parse(
+            r#"{"title": "app", "ports": [80, 443], "db": {"host": "localhost", "max size": 1.5},
+                "users": [{"name": "a"}, {"name": "b\"c"}], "point": [{"x": 1}, 2]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_toml(&value),
+            Ok(r#"title = "app"
+ports = [80, 443]
+point = [{ x = 1 }, 2]
+
+[db]
+host = "localhost"
+"max size" = 1.5
+
+[[users]]
+name = "a"
+
+[[users]]
+name = "b\"c"
+"#
+            .to_string())
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn to_toml_rejects_null() {
+        let value = crate::parse(r#"{"a": {"b": [1, null]}}"#).unwrap();
+        let error = to_toml(&value).unwrap_err();
+        assert_eq!(error, ConvertError::Null("/a/b/1".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "TOML cannot represent the null at '/a/b/1'"
+        );
+        assert_eq!(to_toml(&JsonValue::Null), Err(ConvertError::NotATable));
+    }
+}
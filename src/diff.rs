@@ -0,0 +1,197 @@
+use crate::formatter;
+use crate::parser::JsonValue;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum DiffOp {
+    /// `old[.0]` and `new[.1]` are equal.
+    Equal(usize, usize),
+    /// `old[.0]` is not present in `new`.
+    Delete(usize),
+    /// `new[.0]` is not present in `old`.
+    Insert(usize),
+}
+
+/// The largest LCS table `diff` builds, in cells. Beyond it the changed
+/// middle of the input is paired line by line instead.
+const MAX_TABLE_CELLS: usize = 4_000_000;
+
+/// Computes a longest-common-subsequence diff turning `old` into `new`.
+///
+/// The common prefix and suffix are matched directly, so only the changed
+/// middle needs a table. If that table would exceed `MAX_TABLE_CELLS`, the
+/// middle is paired up position by position, which is still a valid diff but
+/// not necessarily the shortest.
+pub(crate) fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+
+    let mut ops: Vec<DiffOp> = (0..prefix).map(|i| DiffOp::Equal(i, i)).collect();
+    let middle = (old_end - prefix).saturating_mul(new_end - prefix);
+    if middle <= MAX_TABLE_CELLS {
+        diff_table(old, new, prefix, old_end, new_end, &mut ops);
+    } else {
+        diff_pairwise(old, new, prefix, old_end, new_end, &mut ops);
+    }
+    ops.extend((0..suffix).map(|k| DiffOp::Equal(old_end + k, new_end + k)));
+
+    ops
+}
+
+/// Diffs `old[start..old_end]` against `new[start..new_end]` with an LCS table.
+fn diff_table<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+    ops: &mut Vec<DiffOp>,
+) {
+    let (rows, columns) = (old_end - start, new_end - start);
+    // lengths[i][j] is the LCS length of the middle of old[i..] and new[j..].
+    let mut lengths = vec![vec![0usize; columns + 1]; rows + 1];
+    for i in (0..rows).rev() {
+        for j in (0..columns).rev() {
+            lengths[i][j] = if old[start + i] == new[start + j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < rows && j < columns {
+        if old[start + i] == new[start + j] {
+            ops.push(DiffOp::Equal(start + i, start + j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            ops.push(DiffOp::Delete(start + i));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(start + j));
+            j += 1;
+        }
+    }
+    ops.extend((start + i..old_end).map(DiffOp::Delete));
+    ops.extend((start + j..new_end).map(DiffOp::Insert));
+}
+
+/// Diffs `old[start..old_end]` against `new[start..new_end]` by comparing the
+/// lines at the same offset, for inputs too large for `diff_table`.
+fn diff_pairwise<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+    ops: &mut Vec<DiffOp>,
+) {
+    let paired = (old_end - start).min(new_end - start);
+    for k in start..start + paired {
+        if old[k] == new[k] {
+            ops.push(DiffOp::Equal(k, k));
+        } else {
+            ops.push(DiffOp::Delete(k));
+            ops.push(DiffOp::Insert(k));
+        }
+    }
+    ops.extend((start + paired..old_end).map(DiffOp::Delete));
+    ops.extend((start + paired..new_end).map(DiffOp::Insert));
+}
+
+/// Formats `old` and `new` and renders the difference between them line by line,
+/// like a unified diff: unchanged lines start with a space, removed lines with
+/// `-` and added lines with `+`. Every line is shown, including unchanged ones.
+pub fn format_diff(old: &JsonValue, new: &JsonValue) -> String {
+    let old = formatter::format(old);
+    let new = formatter::format(new);
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let mut output = String::new();
+    for op in diff(&old_lines, &new_lines) {
+        let (prefix, line) = match op {
+            DiffOp::Equal(i, _) => (' ', old_lines[i]),
+            DiffOp::Delete(i) => ('-', old_lines[i]),
+            DiffOp::Insert(j) => ('+', new_lines[j]),
+        };
+        output.push(prefix);
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines() {
+        let old = ["a", "b", "c"];
+        let new = ["a", "x", "c", "d"];
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                DiffOp::Equal(0, 0),
+                DiffOp::Delete(1),
+                DiffOp::Insert(1),
+                DiffOp::Equal(2, 2),
+                DiffOp::Insert(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_large_inputs() {
+        let old: Vec<usize> = (0..100_000).collect();
+        let mut new = old.clone();
+        new[10] = 0;
+        new.insert(50_000, 7);
+        let ops = diff(&old, &new);
+        assert_eq!(
+            ops[..10],
+            (0..10).map(|i| DiffOp::Equal(i, i)).collect::<Vec<_>>()[..]
+        );
+        assert_eq!(ops[10..12], [DiffOp::Delete(10), DiffOp::Insert(10)]);
+        assert_eq!(ops.last(), Some(&DiffOp::Equal(99_999, 100_000)));
+
+        // Replaying the ops must rebuild `new` from `old`.
+        let rebuilt: Vec<usize> = ops
+            .iter()
+            .filter_map(|op| match op {
+                DiffOp::Equal(i, _) => Some(old[*i]),
+                DiffOp::Delete(_) => None,
+                DiffOp::Insert(j) => Some(new[*j]),
+            })
+            .collect();
+        assert_eq!(rebuilt, new);
+    }
+
+    #[test]
+    fn format_diff_documents() {
+        let old = crate::parse(r#"{"name": "api", "port": 80, "tags": ["a"]}"#).unwrap();
+        let new = crate::parse(r#"{"name": "api", "port": 8080, "tags": ["a", "b"]}"#).unwrap();
+        assert_eq!(
+            format_diff(&old, &new),
+            r#" {
+   "name": "api",
+-  "port": 80,
++  "port": 8080,
+   "tags": [
+-    "a"
++    "a",
++    "b"
+   ]
+ }
+"#
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::diff::{diff, DiffOp};
+use crate::error::Error;
+use crate::formatter::FormatOptions;
+
+/// Replaces the bytes `start..end` of the original content with `replacement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+}
+
+/// Computes the edits that turn `content` into its formatted form.
+///
+/// Edits are line based, sorted by position and never overlap, so unchanged
+/// lines are left alone. Apply them back to front to keep offsets valid.
+pub fn format_edits(content: &str, options: &FormatOptions) -> Result<Vec<TextEdit>, Error> {
+    let formatted = crate::format_str(content, options)?;
+
+    let old_lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = formatted.split_inclusive('\n').collect();
+
+    let mut edits: Vec<TextEdit> = Vec::new();
+    let mut offset = 0;
+    let mut pending: Option<TextEdit> = None;
+
+    for op in diff(&old_lines, &new_lines) {
+        match op {
+            DiffOp::Equal(old_index, _) => {
+                edits.extend(pending.take());
+                offset += old_lines[old_index].len();
+            }
+            DiffOp::Delete(old_index) => {
+                let edit = pending.get_or_insert_with(|| TextEdit {
+                    start: offset,
+                    end: offset,
+                    replacement: String::new(),
+                });
+                offset += old_lines[old_index].len();
+                edit.end = offset;
+            }
+            DiffOp::Insert(new_index) => {
+                pending
+                    .get_or_insert_with(|| TextEdit {
+                        start: offset,
+                        end: offset,
+                        replacement: String::new(),
+                    })
+                    .replacement
+                    .push_str(new_lines[new_index]);
+            }
+        }
+    }
+    edits.extend(pending);
+
+    Ok(edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(content: &str, edits: &[TextEdit]) -> String {
+        let mut result = content.to_string();
+        for edit in edits.iter().rev() {
+            result.replace_range(edit.start..edit.end, &edit.replacement);
+        }
+        result
+    }
+
+    #[test]
+    fn format_edits_only_touch_changed_lines() {
+        let content = "{\n  \"a\": 1,\n    \"b\": 2,\n  \"c\": 3\n}";
+        let edits = format_edits(content, &FormatOptions::default()).unwrap();
+        assert_eq!(
+            edits,
+            vec![TextEdit {
+                start: 12,
+                end: 24,
+                replacement: "  \"b\": 2,\n".to_string(),
+            }]
+        );
+        assert_eq!(
+            apply(content, &edits),
+            crate::format_str(content, &FormatOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn format_edits_apply_to_formatted_output() {
+        let content = "{\"a\":[1,2],\n\"b\":{\"c\":null}}\n";
+        let edits = format_edits(content, &FormatOptions::default()).unwrap();
+        assert_eq!(
+            apply(content, &edits),
+            crate::format_str(content, &FormatOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn format_edits_formatted_content() {
+        let content = "[\n  1\n]";
+        let edits = format_edits(content, &FormatOptions::default()).unwrap();
+        assert_eq!(edits, vec![]);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::parser::JsonParserError;
+use crate::tokenizer::JsonTokenizeError;
+
+/// Where in the input an error was found, when it is known.
+///
+/// `offset` is in bytes. Lines and columns start at 1, and columns count
+/// characters, so a tab counts as one column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// The position of the byte at `offset` in `content`, which may be
+    /// `content.len()` for the end of the input.
+    pub fn at(content: &str, offset: usize) -> Position {
+        let before = &content[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Position {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Tokenize(JsonTokenizeError, Option<Position>),
+    Parse(JsonParserError, Option<Position>),
+    Io(std::io::Error),
+}
+
+impl Error {
+    /// The line and column the error was found at, if it came from the input
+    /// and its position is known.
+    ///
+    /// Tokenize errors are reported at the start of the token or comment they
+    /// were found in, such as a malformed literal or a string with a bad escape.
+    /// Parse errors are reported at the start of the unexpected token, or at the
+    /// end of the input if it ends too early. Errors found after parsing, such as duplicate keys, have no
+    /// position.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Tokenize(_, position) | Error::Parse(_, position) => {
+                position.map(|position| (position.line, position.column))
+            }
+            Error::Io(_) => None,
+        }
+    }
+}
+
+/// I/O errors are compared by their kind, since `std::io::Error` has no equality.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::Tokenize(a, a_position), Error::Tokenize(b, b_position)) => {
+                a == b && a_position == b_position
+            }
+            (Error::Parse(a, a_position), Error::Parse(b, b_position)) => {
+                a == b && a_position == b_position
+            }
+            (Error::Io(a), Error::Io(b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Tokenize(error, _) => write!(f, "{}", error),
+            Error::Parse(error, _) => write!(f, "{}", error),
+            Error::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<JsonTokenizeError> for Error {
+    fn from(error: JsonTokenizeError) -> Self {
+        Error::Tokenize(error, None)
+    }
+}
+
+impl From<JsonParserError> for Error {
+    fn from(error: JsonParserError) -> Self {
+        Error::Parse(error, None)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
@@ -0,0 +1,238 @@
+use crate::error::Error;
+use crate::parser::{unexpected_key, JsonParserError, JsonValue};
+use crate::tokenizer::{spanned_tokens, JsonToken, SpannedTokens, TokenizeOptions};
+
+/// A step through a JSON document, as emitted by `JsonEvents`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    StartObject,
+    /// An object key. The next event starts or is its value.
+    Key(String),
+    EndObject,
+    StartArray,
+    EndArray,
+    /// A scalar: null, a boolean, a number or a string.
+    Value(JsonValue),
+}
+
+/// What the next token may be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    /// A value, or `]` right after `[`.
+    FirstValue,
+    Value,
+    /// A key, or `}` right after `{`.
+    FirstKey,
+    Key,
+    Colon,
+    CommaOrEnd,
+    /// The top-level value is complete.
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Array,
+    Object,
+}
+
+/// Iterates over a document as a sequence of events, SAX style, without building
+/// the tree, so fields can be picked out of large documents cheaply.
+///
+/// Structural errors are reported when they are reached, after the events that
+/// precede them. Iteration stops after the first error.
+pub struct JsonEvents<'a> {
+    tokens: SpannedTokens<'a>,
+    stack: Vec<Container>,
+    expect: Expect,
+    failed: bool,
+}
+
+static STRICT: TokenizeOptions = TokenizeOptions {
+    allow_numeric_separators: false,
+    allow_hex_numbers: false,
+    allow_lax_numbers: false,
+    allow_control_characters: false,
+    allow_comments: false,
+    case_insensitive_literals: false,
+    replacement_char: None,
+    max_tokens: None,
+    python_literals: false,
+    allow_single_quotes: false,
+};
+
+impl<'a> JsonEvents<'a> {
+    pub fn new(content: &'a str) -> Self {
+        JsonEvents::with_options(content, &STRICT)
+    }
+
+    pub fn with_options(content: &'a str, options: &'a TokenizeOptions) -> Self {
+        JsonEvents {
+            tokens: spanned_tokens(content, options),
+            stack: Vec::new(),
+            expect: Expect::Value,
+            failed: false,
+        }
+    }
+
+    fn event(&mut self, token: JsonToken) -> Result<Option<JsonEvent>, JsonParserError> {
+        let event = match (self.expect, token) {
+            (Expect::Value | Expect::FirstValue, JsonToken::LeftSquareBracket) => {
+                self.stack.push(Container::Array);
+                self.expect = Expect::FirstValue;
+                JsonEvent::StartArray
+            }
+            (Expect::Value | Expect::FirstValue, JsonToken::LeftCurlyBracket) => {
+                self.stack.push(Container::Object);
+                self.expect = Expect::FirstKey;
+                JsonEvent::StartObject
+            }
+            (Expect::FirstValue, JsonToken::RightSquareBracket) => {
+                self.stack.pop();
+                self.end(JsonEvent::EndArray)
+            }
+            (Expect::Value | Expect::FirstValue, token) => {
+                let value = match token {
+                    JsonToken::Null => JsonValue::Null,
+                    JsonToken::True => JsonValue::Bool(true),
+                    JsonToken::False => JsonValue::Bool(false),
+                    JsonToken::Number(number) => JsonValue::Number(number),
+                    JsonToken::String(string) => JsonValue::String(string),
+                    token => return Err(JsonParserError::ExpectedValue(token)),
+                };
+                self.end(JsonEvent::Value(value))
+            }
+            (Expect::FirstKey | Expect::Key, JsonToken::String(key)) => {
+                self.expect = Expect::Colon;
+                JsonEvent::Key(key)
+            }
+            (Expect::FirstKey, JsonToken::RightCurlyBracket) => {
+                self.stack.pop();
+                self.end(JsonEvent::EndObject)
+            }
+            (Expect::FirstKey | Expect::Key, token) => return Err(unexpected_key(&token)),
+            (Expect::Colon, JsonToken::Colon) => {
+                self.expect = Expect::Value;
+                return Ok(None);
+            }
+            (Expect::CommaOrEnd, JsonToken::Comma) => {
+                self.expect = match self.stack.last() {
+                    Some(Container::Object) => Expect::Key,
+                    _ => Expect::Value,
+                };
+                return Ok(None);
+            }
+            (Expect::CommaOrEnd, JsonToken::RightSquareBracket)
+                if self.stack.last() == Some(&Container::Array) =>
+            {
+                self.stack.pop();
+                self.end(JsonEvent::EndArray)
+            }
+            (Expect::CommaOrEnd, JsonToken::RightCurlyBracket)
+                if self.stack.last() == Some(&Container::Object) =>
+            {
+                self.stack.pop();
+                self.end(JsonEvent::EndObject)
+            }
+            (_, token) => return Err(JsonParserError::UnexpectedToken(token)),
+        };
+        Ok(Some(event))
+    }
+
+    /// Returns the event that completes a value, and expects what follows it.
+    fn end(&mut self, event: JsonEvent) -> JsonEvent {
+        self.expect = if self.stack.is_empty() {
+            Expect::Done
+        } else {
+            Expect::CommaOrEnd
+        };
+        event
+    }
+}
+
+impl Iterator for JsonEvents<'_> {
+    type Item = Result<JsonEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            let result = match self.tokens.next() {
+                Some(Ok(spanned)) => self.event(spanned.token).map_err(Error::from),
+                Some(Err(error)) => Err(error.into()),
+                None if self.expect == Expect::Done => return None,
+                None => Err(JsonParserError::UnexpectedEndOfInput.into()),
+            };
+            match result {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => {}
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonNumber;
+
+    #[test]
+    fn events_of_nested_document() {
+        let events: Result<Vec<_>, _> = JsonEvents::new(r#"{"a":[1,2],"b":{},"c":[]}"#).collect();
+        assert_eq!(
+            events,
+            Ok(vec![
+                JsonEvent::StartObject,
+                JsonEvent::Key("a".to_string()),
+                JsonEvent::StartArray,
+                JsonEvent::Value(JsonValue::Number(JsonNumber::Integer(1))),
+                JsonEvent::Value(JsonValue::Number(JsonNumber::Integer(2))),
+                JsonEvent::EndArray,
+                JsonEvent::Key("b".to_string()),
+                JsonEvent::StartObject,
+                JsonEvent::EndObject,
+                JsonEvent::Key("c".to_string()),
+                JsonEvent::StartArray,
+                JsonEvent::EndArray,
+                JsonEvent::EndObject,
+            ])
+        );
+    }
+
+    #[test]
+    fn events_stop_at_first_error() {
+        let events: Vec<_> = JsonEvents::new(r#"[true, ]"#).collect();
+        assert_eq!(
+            events,
+            vec![
+                Ok(JsonEvent::StartArray),
+                Ok(JsonEvent::Value(JsonValue::Bool(true))),
+                Err(Error::Parse(
+                    JsonParserError::ExpectedValue(JsonToken::RightSquareBracket),
+                    None
+                )),
+            ]
+        );
+
+        let events: Vec<_> = JsonEvents::new(r#"{"a": 1"#).collect();
+        assert_eq!(
+            events.last(),
+            Some(&Err(Error::Parse(
+                JsonParserError::UnexpectedEndOfInput,
+                None
+            )))
+        );
+
+        let events: Vec<_> = JsonEvents::new("1 2").collect();
+        assert_eq!(
+            events.last(),
+            Some(&Err(Error::Parse(
+                JsonParserError::UnexpectedToken(JsonToken::Number(JsonNumber::Integer(2))),
+                None
+            )))
+        );
+    }
+}
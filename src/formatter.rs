@@ -1,153 +1,2302 @@
-use crate::parser::JsonValue;
+use crate::parser::{JsonNumber, JsonValue};
+use std::cmp::Ordering;
+use std::fmt;
+use std::io;
+use std::ops::RangeInclusive;
+
+/// The case of the hex digits in `\uXXXX` escapes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum HexCase {
+    #[default]
+    Lower,
+    Upper,
+}
+
+/// The quote character around keys and strings.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum QuoteStyle {
+    /// `"`, the only quote standard JSON allows.
+    #[default]
+    Double,
+    /// `'`, as JSON5 allows. The output is then not valid JSON.
+    Single,
+}
+
+impl QuoteStyle {
+    /// Returns the style quoting with `quote`, or `None` if it is neither `"` nor `'`.
+    pub fn from_char(quote: char) -> Option<QuoteStyle> {
+        match quote {
+            '"' => Some(QuoteStyle::Double),
+            '\'' => Some(QuoteStyle::Single),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(self) -> char {
+        match self {
+            QuoteStyle::Double => '"',
+            QuoteStyle::Single => '\'',
+        }
+    }
+}
+
+/// Where `array_comma_placement` puts the comma between two array elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CommaPlacement {
+    /// At the end of the line of the earlier element.
+    #[default]
+    Trailing,
+    /// At the start of the line of the later element, followed by a space.
+    Leading,
+}
+
+/// How `sort_keys` orders object entries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyOrder {
+    /// By code point, so all upper case letters come before lower case ones.
+    CodePoint,
+    /// By the lower case form of the key.
+    CaseInsensitive,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    /// The string used for one level of indentation.
+    pub indent: String,
+    /// The string used to indent the elements of arrays, if different from
+    /// `indent`, which then only applies to object entries. Each line is indented
+    /// by the units of all its enclosing containers, outermost first, so an array
+    /// inside an object is indented by `indent` followed by `array_indent`.
+    pub array_indent: Option<String>,
+    /// Separate the entries of the top-level object with an empty line.
+    pub blank_line_between_entries: bool,
+    /// Escape U+2028, U+2029 and `</` so the output can be embedded in an HTML `<script>`.
+    pub escape_js_unsafe: bool,
+    /// Render the whole value on a single line without indentation.
+    pub compact: bool,
+    /// In compact mode, write empty containers as `{ }`/`[ ]` and separate
+    /// elements with `, ` instead of `{}`/`[]` and `,`.
+    pub compact_spacing: bool,
+    /// Write arrays containing only scalars on one line, as `[1, 2, 3]`, when the
+    /// line fits within this many columns. The width counts the indentation, the
+    /// key and the array itself, but not a trailing comma.
+    pub inline_array_width: Option<usize>,
+    /// Write any array or object on one line, as `{"a": [1, 2]}`, when the line
+    /// fits within this many columns, and break it onto multiple lines otherwise.
+    /// Width is measured like `inline_array_width`.
+    pub max_line_width: Option<usize>,
+    /// The number of columns a tab counts for in width calculations. This does
+    /// not change the emitted indentation.
+    pub tab_width: usize,
+    /// Write arrays of objects that all have the same keys in the same order as a
+    /// table, one object per line. Values are padded after their comma so that the
+    /// columns line up, which keeps the output valid JSON.
+    pub table_arrays: bool,
+    /// The case of the hex digits in the `\uXXXX` escapes written for control
+    /// characters and, with `escape_js_unsafe`, U+2028 and U+2029.
+    pub hex_case: HexCase,
+    /// The text between an object key and its value. Defaults to `": "`, or `":"`
+    /// in compact mode.
+    pub kv_separator: Option<String>,
+    /// Sort arrays whose elements are all strings (by code point) or all numbers
+    /// (ascending). Arrays mixing types or containing containers keep their order.
+    pub sort_scalar_arrays: bool,
+    /// Sort the entries of every object by key. Keys that compare equal keep their
+    /// original relative order.
+    pub sort_keys: Option<KeyOrder>,
+    /// Apply `sort_keys` only to objects nested at these depths, where the
+    /// top-level value is at depth 0 and each enclosing array or object adds one.
+    pub sort_depth: Option<RangeInclusive<usize>>,
+    /// Show at most this many elements of each array, followed by a marker such
+    /// as `... (1234 more)`. This is meant for previews: when any element is
+    /// left out, the output is not valid JSON.
+    pub max_array_elements: Option<usize>,
+    /// Like `max_array_elements`, for the entries of objects.
+    pub max_object_entries: Option<usize>,
+    /// Write a comma after the last element of every array and object that spans
+    /// multiple lines, as JSON5 allows, so appending an element only changes one
+    /// line in a diff. The output is then not valid JSON.
+    pub trailing_comma: bool,
+    /// Write floats as JavaScript's `Number.prototype.toString` does, so output
+    /// matches `JSON.stringify`: `100` rather than `100.0`, and `1e+21` rather
+    /// than `1000000000000000000000`. Integers are written as they are.
+    pub ecmascript_numbers: bool,
+    /// Start the output with a UTF-8 byte order mark, U+FEFF, for consumers that
+    /// require one. Only `format_with_options`, `format_into`, `format_into_slice`
+    /// and `format_with_comments` write it, once at the start of the document.
+    pub emit_bom: bool,
+    /// Where to put the commas between the elements of arrays that span multiple
+    /// lines. Objects always use trailing commas.
+    pub array_comma_placement: CommaPlacement,
+    /// Write floats with an integral value without a fractional part, as `5`
+    /// rather than `5.0`. Other trailing zeros, as in `1.2300`, are never written
+    /// since numbers are stored as `f64`. `ecmascript_numbers` implies this.
+    pub strip_trailing_zeros: bool,
+    /// Start each element of a top-level array with a comment giving its index,
+    /// as `/* [3] */ {...}`, to match elements to lines in a preview. Comments
+    /// are not valid JSON, though JSONC readers accept them.
+    pub index_comments: bool,
+    /// The quote around keys and strings. Only the quote in use is escaped inside
+    /// them, so with single quotes `"` is written as is and `'` as `\'`.
+    pub quote: QuoteStyle,
+    /// Write the entries of the top-level array or object one per line, but each
+    /// of their values in compact form, as in `{\n  "a": {"x":1}\n}`.
+    pub shallow: bool,
+    /// Group the digits of the integer part of numbers in threes with this
+    /// character, as `1,000,000.5`, for previews meant for people. The output is
+    /// then not valid JSON.
+    pub thousands_separator: Option<char>,
+    /// Write arrays containing only scalars with this many elements per line,
+    /// padded after their comma so that the columns line up, as for
+    /// `table_arrays`. Commas stay at the end of elements whatever
+    /// `array_comma_placement` says.
+    pub array_items_per_line: Option<usize>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: "  ".to_string(),
+            array_indent: None,
+            blank_line_between_entries: false,
+            escape_js_unsafe: false,
+            compact: false,
+            compact_spacing: false,
+            inline_array_width: None,
+            max_line_width: None,
+            tab_width: 4,
+            table_arrays: false,
+            hex_case: HexCase::Lower,
+            kv_separator: None,
+            sort_scalar_arrays: false,
+            sort_keys: None,
+            sort_depth: None,
+            max_array_elements: None,
+            max_object_entries: None,
+            trailing_comma: false,
+            ecmascript_numbers: false,
+            emit_bom: false,
+            array_comma_placement: CommaPlacement::Trailing,
+            strip_trailing_zeros: false,
+            index_comments: false,
+            quote: QuoteStyle::Double,
+            shallow: false,
+            thousands_separator: None,
+            array_items_per_line: None,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Writes the whole value on one line with a space after each `:` and `,`, as
+    /// `{"a": 1, "b": [1, 2]}`, for log lines that must stay readable. Empty
+    /// containers are written as `{ }` and `[ ]`.
+    pub fn single_line() -> Self {
+        FormatOptions {
+            compact: true,
+            compact_spacing: true,
+            kv_separator: Some(": ".to_string()),
+            ..FormatOptions::default()
+        }
+    }
+}
 
 pub fn format(value: &JsonValue) -> String {
-    format_value(value, 1)
+    format_with_options(value, &FormatOptions::default())
+}
+
+pub fn format_with_options(value: &JsonValue, options: &FormatOptions) -> String {
+    let mut output = String::new();
+    format_into(value, &mut output, options);
+    output
+}
+
+/// Formats `value` like `format_with_options`, but without a byte order mark, for
+/// a value written as part of a larger document.
+pub(crate) fn format_fragment(value: &JsonValue, options: &FormatOptions) -> String {
+    let mut output = String::new();
+    format_value(&mut output, value, &Indent::ROOT, 0, options).unwrap();
+    output
+}
+
+/// Formats `value` into `buf`, replacing its contents but keeping its allocation,
+/// so a loop formatting many documents can reuse one buffer.
+pub fn format_into(value: &JsonValue, buf: &mut String, options: &FormatOptions) {
+    buf.clear();
+    if options.emit_bom {
+        buf.push('\u{FEFF}');
+    }
+    format_value(buf, value, &Indent::ROOT, 0, options).unwrap();
+}
+
+/// The error of `format_into_slice` when the output doesn't fit in the buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BufferFull;
+
+impl fmt::Display for BufferFull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Output does not fit in the buffer")
+    }
+}
+
+impl std::error::Error for BufferFull {}
+
+/// Formats `value` into `buf` without allocating and returns the number of bytes
+/// written. If the output is longer than `buf`, the contents of `buf` are
+/// unspecified.
+pub fn format_into_slice(
+    value: &JsonValue,
+    buf: &mut [u8],
+    options: &FormatOptions,
+) -> Result<usize, BufferFull> {
+    let mut out = SliceWriter { buf, len: 0 };
+    if options.emit_bom {
+        fmt::Write::write_char(&mut out, '\u{FEFF}').map_err(|_| BufferFull)?;
+    }
+    format_value(&mut out, value, &Indent::ROOT, 0, options).map_err(|_| BufferFull)?;
+    Ok(out.len)
+}
+
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        let target = self.buf.get_mut(self.len..end).ok_or(fmt::Error)?;
+        target.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl LineWriter for SliceWriter<'_> {}
+
+/// Remembers the last formatted output, so formatting the same value with the
+/// same options again, as when re-rendering a view, doesn't redo the work.
+///
+/// The value and options are compared with `==` against those of the last call,
+/// which is much cheaper than formatting but still visits the whole value.
+#[derive(Debug, Clone, Default)]
+pub struct FormattedCache {
+    last: Option<(JsonValue, FormatOptions, String)>,
+}
+
+impl FormattedCache {
+    pub fn new() -> Self {
+        FormattedCache::default()
+    }
+
+    /// Returns `value` formatted with `options`, from the cache if neither has
+    /// changed since the last call.
+    pub fn format(&mut self, value: &JsonValue, options: &FormatOptions) -> &str {
+        let hit = matches!(
+            &self.last,
+            Some((last_value, last_options, _)) if last_value == value && last_options == options
+        );
+        if !hit {
+            self.last = None;
+        }
+        let (_, _, formatted) = self.last.get_or_insert_with(|| {
+            let formatted = format_with_options(value, options);
+            (value.clone(), options.clone(), formatted)
+        });
+        formatted
+    }
+}
+
+/// Writes the values yielded by `iter` to `writer` as a JSON array, one element
+/// at a time, so the whole sequence never has to be held in memory.
+///
+/// The output is identical to formatting a `JsonValue::Array` of the same values.
+pub fn format_array_from_iter<W: io::Write, I: Iterator<Item = JsonValue>>(
+    writer: &mut W,
+    iter: I,
+    options: &FormatOptions,
+) -> io::Result<()> {
+    let mut iter = iter.peekable();
+
+    if iter.peek().is_none() {
+        writer.write_all(empty_container("[]", options).as_bytes())?;
+        return writer.flush();
+    }
+
+    writer.write_all(b"[")?;
+    let inner = Indent::ROOT.nested(array_indent(options));
+    let mut element = String::new();
+    for (index, value) in iter.enumerate() {
+        element.clear();
+        let column = format_element_start(&mut element, index, &inner, options).unwrap();
+        format_value(&mut element, &value, &inner, column, options).unwrap();
+        writer.write_all(element.as_bytes())?;
+        writer.flush()?;
+    }
+    element.clear();
+    format_closing_line_break(&mut element, &Indent::ROOT, options).unwrap();
+    element.push(']');
+    writer.write_all(element.as_bytes())?;
+    writer.flush()
+}
+
+/// Writes each value yielded by `iter` to `writer` as newline-delimited JSON:
+/// minified on a single line and followed by `\n`.
+///
+/// `options` is used in compact mode regardless of its `compact` field, so every
+/// line is an independent JSON document.
+pub fn write_ndjson<W: io::Write, I: Iterator<Item = JsonValue>>(
+    writer: &mut W,
+    iter: I,
+    options: &FormatOptions,
+) -> io::Result<()> {
+    let options = FormatOptions {
+        compact: true,
+        ..options.clone()
+    };
+    let mut line = String::new();
+    for value in iter {
+        line.clear();
+        format_value(&mut line, &value, &Indent::ROOT, 0, &options).unwrap();
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Formats `value` into `out`, calling `begin_line` at the start of every line so
+/// the caller can decorate lines, for example with line numbers or a gutter.
+///
+/// `begin_line` receives the nesting depth of the line, before the indentation is
+/// written. The first line has depth 0, as do the empty lines written by
+/// `blank_line_between_entries`.
+pub fn format_with_writer<W, F>(
+    out: &mut W,
+    value: &JsonValue,
+    options: &FormatOptions,
+    mut begin_line: F,
+) -> fmt::Result
+where
+    W: fmt::Write,
+    F: FnMut(&mut W, usize) -> fmt::Result,
+{
+    begin_line(out, 0)?;
+    let mut decorated = Decorated { out, begin_line };
+    format_value(&mut decorated, value, &Indent::ROOT, 0, options)
+}
+
+/// The output of the formatter, which is told where each new line begins.
+trait LineWriter: fmt::Write {
+    /// Ends the current line and starts one at `depth`, before its indentation.
+    fn new_line(&mut self, _depth: usize) -> fmt::Result {
+        self.write_char('\n')
+    }
+}
+
+impl LineWriter for String {}
+
+impl LineWriter for ByteCounter {}
+
+impl LineWriter for WidthCounter {}
+
+struct Decorated<'a, W, F> {
+    out: &'a mut W,
+    begin_line: F,
+}
+
+impl<W: fmt::Write, F> fmt::Write for Decorated<'_, W, F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_str(s)
+    }
+}
+
+impl<W: fmt::Write, F: FnMut(&mut W, usize) -> fmt::Result> LineWriter for Decorated<'_, W, F> {
+    fn new_line(&mut self, depth: usize) -> fmt::Result {
+        self.out.write_char('\n')?;
+        (self.begin_line)(self.out, depth)
+    }
+}
+
+impl JsonValue {
+    /// Returns the length in bytes of the formatted value, without building the output.
+    pub fn serialized_len(&self, options: &FormatOptions) -> usize {
+        let mut counter = ByteCounter(if options.emit_bom { 3 } else { 0 });
+        format_value(&mut counter, self, &Indent::ROOT, 0, options).unwrap();
+        counter.0
+    }
+}
+
+struct ByteCounter(usize);
+
+impl fmt::Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Values nested deeper than this are written by `format_deep`, so that formatting
+/// doesn't recurse once per level of arbitrarily deep input.
+const MAX_LAYOUT_DEPTH: usize = 128;
+
+/// `indent` is the indentation of the line the value starts on, and `column` the
+/// width of that line before the value, used for inline layout decisions.
+fn format_value<W: LineWriter>(
+    out: &mut W,
+    value: &JsonValue,
+    indent: &Indent,
+    column: usize,
+    options: &FormatOptions,
+) -> fmt::Result {
+    if indent.depth >= MAX_LAYOUT_DEPTH {
+        return format_deep(out, value, indent.depth, options);
+    }
+    if options.shallow && !options.compact && indent.depth > 0 {
+        let compact = FormatOptions {
+            compact: true,
+            ..options.clone()
+        };
+        return format_value(out, value, indent, column, &compact);
+    }
+    if let Some(sorted) = sorted_scalar_array(value, options) {
+        return format_value(out, &sorted, indent, column, options);
+    }
+    if let Some(sorted) = sorted_object(value, indent.depth, options) {
+        return format_value(out, &sorted, indent, column, options);
+    }
+
+    match value {
+        JsonValue::Null => out.write_str("null"),
+        JsonValue::Bool(b) => write!(out, "{}", b),
+        JsonValue::Number(n) => match options.thousands_separator {
+            Some(separator) => {
+                let mut plain = String::new();
+                format_number(&mut plain, n, options)?;
+                format_grouped_digits(out, &plain, separator)
+            }
+            None => format_number(out, n, options),
+        },
+        JsonValue::String(s) => format_string(out, s, options),
+        JsonValue::Object(_) | JsonValue::Array(_)
+            if fits_inline(value, indent.depth, column, options) =>
+        {
+            format_inline(out, value, indent.depth, options)
+        }
+        JsonValue::Object(entries) => format_object(out, entries, indent, options),
+        JsonValue::Array(values) => format_array(out, values, indent, options),
+    }
+}
+
+fn format_object<W: LineWriter>(
+    out: &mut W,
+    entries: &[(String, JsonValue)],
+    indent: &Indent,
+    options: &FormatOptions,
+) -> fmt::Result {
+    if entries.is_empty() {
+        return out.write_str(empty_container("{}", options));
+    }
+
+    let blank_line = options.blank_line_between_entries && indent.depth == 0;
+    let inner = indent.nested(&options.indent);
+    let (entries, hidden) = elide(entries, options.max_object_entries);
+
+    out.write_str("{")?;
+    for (index, (key, value)) in entries.iter().enumerate() {
+        if index > 0 {
+            format_separator(out, blank_line, options)?;
+        }
+        format_line_break(out, &inner, options)?;
+        format_string(out, key, options)?;
+        out.write_str(kv_separator(options))?;
+        let column = if tracks_width(options) {
+            inner.width(options)
+                + string_width(key, options)
+                + text_width(kv_separator(options), options)
+        } else {
+            0
+        };
+        format_value(out, value, &inner, column, options)?;
+    }
+    if hidden > 0 {
+        format_separator(out, blank_line, options)?;
+        format_line_break(out, &inner, options)?;
+        format_elision(out, hidden)?;
+    }
+    format_closing_line_break(out, indent, options)?;
+    out.write_str("}")
+}
+
+fn format_array<W: LineWriter>(
+    out: &mut W,
+    values: &[JsonValue],
+    indent: &Indent,
+    options: &FormatOptions,
+) -> fmt::Result {
+    if values.is_empty() {
+        return out.write_str(empty_container("[]", options));
+    }
+    let (values, hidden) = elide(values, options.max_array_elements);
+    if options.table_arrays && !options.compact && hidden == 0 {
+        // Rows are matched and laid out with their keys in the order they are written.
+        let sorted: Option<Vec<JsonValue>> = key_order(indent.depth + 1, options).map(|_| {
+            values
+                .iter()
+                .map(|value| {
+                    sorted_object(value, indent.depth + 1, options).unwrap_or_else(|| value.clone())
+                })
+                .collect()
+        });
+        if let Some(rows) = table_rows(sorted.as_deref().unwrap_or(values), options) {
+            return format_table(out, &rows, indent, options);
+        }
+    }
+    if let Some(per_line) = options.array_items_per_line {
+        let scalars_only = values
+            .iter()
+            .all(|value| !matches!(value, JsonValue::Array(_) | JsonValue::Object(_)));
+        if scalars_only && !options.compact && hidden == 0 {
+            return format_grid(out, values, per_line.max(1), indent, options);
+        }
+    }
+
+    let inner = indent.nested(array_indent(options));
+    out.write_str("[")?;
+    for (index, value) in values.iter().enumerate() {
+        let column = format_element_start(out, index, &inner, options)?;
+        format_value(out, value, &inner, column, options)?;
+    }
+    if hidden > 0 {
+        format_element_start(out, values.len(), &inner, options)?;
+        format_elision(out, hidden)?;
+    }
+    format_closing_line_break(out, indent, options)?;
+    out.write_str("]")
+}
+
+/// Returns a sorted copy of `value` if `sort_scalar_arrays` applies to it and it
+/// isn't sorted already.
+fn sorted_scalar_array(value: &JsonValue, options: &FormatOptions) -> Option<JsonValue> {
+    let JsonValue::Array(values) = value else {
+        return None;
+    };
+    if !sorts_scalars(values, options) || values.is_sorted_by(|a, b| compare_scalars(a, b).is_le())
+    {
+        return None;
+    }
+
+    let mut sorted = values.clone();
+    sorted.sort_by(compare_scalars);
+    Some(JsonValue::Array(sorted))
+}
+
+/// Whether `sort_scalar_arrays` applies to an array of `values`: all strings or
+/// all numbers.
+fn sorts_scalars(values: &[JsonValue], options: &FormatOptions) -> bool {
+    options.sort_scalar_arrays
+        && (values
+            .iter()
+            .all(|value| matches!(value, JsonValue::Number(_)))
+            || values
+                .iter()
+                .all(|value| matches!(value, JsonValue::String(_))))
+}
+
+fn compare_scalars(a: &JsonValue, b: &JsonValue) -> Ordering {
+    match (a, b) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => a.as_f64().total_cmp(&b.as_f64()),
+        (JsonValue::String(a), JsonValue::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Returns a copy of `value`, nested at `depth`, with its entries sorted if
+/// `sort_keys` applies to it and it isn't sorted already.
+fn sorted_object(value: &JsonValue, depth: usize, options: &FormatOptions) -> Option<JsonValue> {
+    let JsonValue::Object(entries) = value else {
+        return None;
+    };
+    let order = key_order(depth, options)?;
+    let compare =
+        |a: &(String, JsonValue), b: &(String, JsonValue)| compare_keys(order, &a.0, &b.0);
+    if entries.is_sorted_by(|a, b| compare(a, b).is_le()) {
+        return None;
+    }
+
+    // `sort_by` is stable, so keys that compare equal stay in document order.
+    let mut sorted = entries.clone();
+    sorted.sort_by(compare);
+    Some(JsonValue::Object(sorted))
+}
+
+/// The order `sort_keys` puts the entries of an object nested at `depth` in, if
+/// it applies at that depth.
+fn key_order(depth: usize, options: &FormatOptions) -> Option<KeyOrder> {
+    match &options.sort_depth {
+        Some(depths) if !depths.contains(&depth) => None,
+        _ => options.sort_keys,
+    }
+}
+
+fn compare_keys(order: KeyOrder, a: &str, b: &str) -> Ordering {
+    match order {
+        KeyOrder::CodePoint => a.cmp(b),
+        KeyOrder::CaseInsensitive => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// Returns the entries of each element if `values` are all non-empty objects with
+/// the same keys in the same order, none of them long enough to be elided.
+fn table_rows<'a>(
+    values: &'a [JsonValue],
+    options: &FormatOptions,
+) -> Option<Vec<&'a [(String, JsonValue)]>> {
+    let mut rows: Vec<&[(String, JsonValue)]> = Vec::new();
+    for value in values {
+        let JsonValue::Object(entries) = value else {
+            return None;
+        };
+        if elide(entries, options.max_object_entries).1 > 0 {
+            return None;
+        }
+        let same_keys = match rows.first() {
+            Some(first) => {
+                first.len() == entries.len() && first.iter().zip(entries).all(|(a, b)| a.0 == b.0)
+            }
+            None => !entries.is_empty(),
+        };
+        if !same_keys {
+            return None;
+        }
+        rows.push(entries);
+    }
+    Some(rows)
+}
+
+fn format_table<W: LineWriter>(
+    out: &mut W,
+    rows: &[&[(String, JsonValue)]],
+    indent: &Indent,
+    options: &FormatOptions,
+) -> fmt::Result {
+    let mut cells = Vec::with_capacity(rows.len());
+    for entries in rows {
+        let mut row = Vec::with_capacity(entries.len());
+        for (index, (key, value)) in entries.iter().enumerate() {
+            let mut cell = String::new();
+            format_string(&mut cell, key, options)?;
+            cell.push_str(kv_separator(options));
+            format_inline(&mut cell, value, indent.depth + 2, options)?;
+            if index + 1 < entries.len() {
+                cell.push(',');
+            }
+            row.push(cell);
+        }
+        cells.push(row);
+    }
+
+    let inner = indent.nested(array_indent(options));
+    let mut widths = vec![0; rows[0].len()];
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text_width(cell, options));
+        }
+    }
+
+    out.write_str("[")?;
+    for (index, row) in cells.iter().enumerate() {
+        format_element_start(out, index, &inner, options)?;
+        out.write_char('{')?;
+        for (column, cell) in row.iter().enumerate() {
+            out.write_str(cell)?;
+            if column + 1 < row.len() {
+                let padding = widths[column] - text_width(cell, options);
+                write!(out, "{:1$}", "", padding + 1)?;
+            }
+        }
+        out.write_char('}')?;
+    }
+    format_closing_line_break(out, indent, options)?;
+    out.write_str("]")
+}
+
+fn format_grid<W: LineWriter>(
+    out: &mut W,
+    values: &[JsonValue],
+    per_line: usize,
+    indent: &Indent,
+    options: &FormatOptions,
+) -> fmt::Result {
+    let mut cells = Vec::with_capacity(values.len());
+    for (index, value) in values.iter().enumerate() {
+        let mut cell = String::new();
+        format_inline(&mut cell, value, indent.depth + 1, options)?;
+        if index + 1 < values.len() {
+            cell.push(',');
+        }
+        cells.push(cell);
+    }
+
+    let inner = indent.nested(array_indent(options));
+    let mut widths = vec![0; per_line.min(cells.len())];
+    for (index, cell) in cells.iter().enumerate() {
+        let width = &mut widths[index % per_line];
+        *width = (*width).max(text_width(cell, options));
+    }
+
+    out.write_str("[")?;
+    for row in cells.chunks(per_line) {
+        format_line_break(out, &inner, options)?;
+        for (column, cell) in row.iter().enumerate() {
+            out.write_str(cell)?;
+            if column + 1 < row.len() {
+                let padding = widths[column] - text_width(cell, options);
+                write!(out, "{:1$}", "", padding + 1)?;
+            }
+        }
+    }
+    format_closing_line_break(out, indent, options)?;
+    out.write_str("]")
+}
+
+fn fits_inline(value: &JsonValue, depth: usize, column: usize, options: &FormatOptions) -> bool {
+    if options.compact {
+        return false;
+    }
+
+    let max_width = match value {
+        JsonValue::Array(values) if !values.is_empty() => {
+            let scalars_only = values
+                .iter()
+                .all(|value| !matches!(value, JsonValue::Array(_) | JsonValue::Object(_)));
+            if scalars_only {
+                options.inline_array_width.max(options.max_line_width)
+            } else {
+                options.max_line_width
+            }
+        }
+        JsonValue::Object(entries) if !entries.is_empty() => options.max_line_width,
+        _ => None,
+    };
+    let Some(max_width) = max_width else {
+        return false;
+    };
+
+    let mut width = WidthCounter {
+        width: column,
+        max_width,
+        tab_width: options.tab_width,
+    };
+    format_inline(&mut width, value, depth, options).is_ok()
+}
+
+/// Writes `value`, nested at `depth`, on a single line, with a space after each
+/// `:` and `,`.
+fn format_inline<W: LineWriter>(
+    out: &mut W,
+    value: &JsonValue,
+    depth: usize,
+    options: &FormatOptions,
+) -> fmt::Result {
+    if depth >= MAX_LAYOUT_DEPTH {
+        return format_deep(out, value, depth, options);
+    }
+    if let Some(sorted) = sorted_scalar_array(value, options) {
+        return format_inline(out, &sorted, depth, options);
+    }
+    if let Some(sorted) = sorted_object(value, depth, options) {
+        return format_inline(out, &sorted, depth, options);
+    }
+
+    match value {
+        JsonValue::Array(values) if !values.is_empty() => {
+            let (values, hidden) = elide(values, options.max_array_elements);
+            out.write_char('[')?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.write_str(", ")?;
+                }
+                format_inline(out, value, depth + 1, options)?;
+            }
+            if hidden > 0 {
+                out.write_str(", ")?;
+                format_elision(out, hidden)?;
+            }
+            out.write_char(']')
+        }
+        JsonValue::Object(entries) if !entries.is_empty() => {
+            let (entries, hidden) = elide(entries, options.max_object_entries);
+            out.write_char('{')?;
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    out.write_str(", ")?;
+                }
+                format_string(out, key, options)?;
+                out.write_str(kv_separator(options))?;
+                format_inline(out, value, depth + 1, options)?;
+            }
+            if hidden > 0 {
+                out.write_str(", ")?;
+                format_elision(out, hidden)?;
+            }
+            out.write_char('}')
+        }
+        _ => format_value(out, value, &Indent::ROOT, 0, options),
+    }
+}
+
+/// Writes `value`, nested at `depth`, in compact form using an explicit stack
+/// instead of recursion.
+///
+/// Keys and scalar arrays are sorted and long containers elided as usual, but the
+/// layout options that break lines are not applied to values written this way.
+fn format_deep<W: LineWriter>(
+    out: &mut W,
+    value: &JsonValue,
+    depth: usize,
+    options: &FormatOptions,
+) -> fmt::Result {
+    let options = &FormatOptions {
+        compact: true,
+        ..options.clone()
+    };
+    let mut stack: Vec<DeepContainer> = Vec::new();
+    let mut next = Some(value);
+    loop {
+        if let Some(value) = next.take() {
+            match DeepContainer::new(value, depth + stack.len(), options) {
+                Some(container) => {
+                    out.write_char(container.brackets[0])?;
+                    stack.push(container);
+                }
+                None => format_value(out, value, &Indent::ROOT, 0, options)?,
+            }
+        }
+
+        let Some(open) = stack.last_mut() else {
+            return Ok(());
+        };
+        match open.elements.next() {
+            Some((key, value)) => {
+                if open.started {
+                    format_separator(out, false, options)?;
+                }
+                open.started = true;
+                if let Some(key) = key {
+                    format_string(out, key, options)?;
+                    out.write_str(kv_separator(options))?;
+                }
+                next = Some(value);
+            }
+            None => {
+                if open.hidden > 0 {
+                    if open.started {
+                        format_separator(out, false, options)?;
+                    }
+                    format_elision(out, open.hidden)?;
+                }
+                out.write_char(open.brackets[1])?;
+                stack.pop();
+            }
+        }
+    }
+}
+
+/// An array or object open in `format_deep`.
+struct DeepContainer<'a> {
+    /// The elements still to write, with their keys for an object.
+    elements: std::vec::IntoIter<(Option<&'a str>, &'a JsonValue)>,
+    /// The number of elements left out by elision.
+    hidden: usize,
+    brackets: [char; 2],
+    /// Whether an element has been written yet.
+    started: bool,
+}
+
+impl<'a> DeepContainer<'a> {
+    /// Returns the elements of `value`, nested at `depth`, sorted and elided as
+    /// `options` say, or `None` if it is a scalar or an empty container.
+    fn new(value: &'a JsonValue, depth: usize, options: &FormatOptions) -> Option<Self> {
+        let mut elements: Vec<(Option<&str>, &JsonValue)>;
+        let (limit, brackets) = match value {
+            JsonValue::Array(values) if !values.is_empty() => {
+                elements = values.iter().map(|value| (None, value)).collect();
+                if sorts_scalars(values, options) {
+                    elements.sort_by(|a, b| compare_scalars(a.1, b.1));
+                }
+                (options.max_array_elements, ['[', ']'])
+            }
+            JsonValue::Object(entries) if !entries.is_empty() => {
+                elements = entries
+                    .iter()
+                    .map(|(key, value)| (Some(key.as_str()), value))
+                    .collect();
+                if let Some(order) = key_order(depth, options) {
+                    // Stable, so keys that compare equal stay in document order.
+                    elements.sort_by(|a, b| compare_keys(order, a.0.unwrap(), b.0.unwrap()));
+                }
+                (options.max_object_entries, ['{', '}'])
+            }
+            _ => return None,
+        };
+        let hidden = elide(&elements, limit).1;
+        elements.truncate(elements.len() - hidden);
+        Some(DeepContainer {
+            elements: elements.into_iter(),
+            hidden,
+            brackets,
+            started: false,
+        })
+    }
+}
+
+/// Counts the columns written to it, counting a tab as `tab_width` columns, and
+/// fails as soon as `max_width` is exceeded.
+struct WidthCounter {
+    width: usize,
+    max_width: usize,
+    tab_width: usize,
+}
+
+impl fmt::Write for WidthCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.width += s
+            .chars()
+            .map(|char| if char == '\t' { self.tab_width } else { 1 })
+            .sum::<usize>();
+        if self.width > self.max_width {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+/// Whether layout depends on line width, which is otherwise not worth measuring.
+fn tracks_width(options: &FormatOptions) -> bool {
+    options.inline_array_width.is_some() || options.max_line_width.is_some()
+}
+
+/// The number of columns `text` occupies, counting a tab as `tab_width` columns.
+fn text_width(text: &str, options: &FormatOptions) -> usize {
+    text.chars()
+        .map(|char| if char == '\t' { options.tab_width } else { 1 })
+        .sum()
+}
+
+/// The indentation of a line: one unit for each enclosing container, outermost first.
+struct Indent<'a> {
+    parent: Option<&'a Indent<'a>>,
+    unit: &'a str,
+    depth: usize,
+}
+
+impl<'a> Indent<'a> {
+    const ROOT: Indent<'static> = Indent {
+        parent: None,
+        unit: "",
+        depth: 0,
+    };
+
+    fn nested(&'a self, unit: &'a str) -> Indent<'a> {
+        Indent {
+            parent: Some(self),
+            unit,
+            depth: self.depth + 1,
+        }
+    }
+
+    fn write<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        if let Some(parent) = self.parent {
+            parent.write(out)?;
+        }
+        out.write_str(self.unit)
+    }
+
+    fn width(&self, options: &FormatOptions) -> usize {
+        if !tracks_width(options) {
+            return 0;
+        }
+        let parent = self.parent.map_or(0, |parent| parent.width(options));
+        parent + text_width(self.unit, options)
+    }
+}
+
+fn array_indent(options: &FormatOptions) -> &str {
+    options.array_indent.as_deref().unwrap_or(&options.indent)
+}
+
+fn string_width(string: &str, options: &FormatOptions) -> usize {
+    let mut quoted = String::new();
+    format_string(&mut quoted, string, options).unwrap();
+    text_width(&quoted, options)
+}
+
+/// Splits `items` into those shown under `limit` and the number left out.
+fn elide<T>(items: &[T], limit: Option<usize>) -> (&[T], usize) {
+    match limit {
+        Some(limit) if items.len() > limit => (&items[..limit], items.len() - limit),
+        _ => (items, 0),
+    }
+}
+
+fn format_elision<W: fmt::Write>(out: &mut W, hidden: usize) -> fmt::Result {
+    write!(out, "... ({} more)", hidden)
+}
+
+fn kv_separator(options: &FormatOptions) -> &str {
+    match &options.kv_separator {
+        Some(separator) => separator,
+        None if options.compact => ":",
+        None => ": ",
+    }
+}
+
+fn empty_container(brackets: &'static str, options: &FormatOptions) -> &'static str {
+    match (brackets, options.compact && options.compact_spacing) {
+        ("{}", true) => "{ }",
+        ("[]", true) => "[ ]",
+        _ => brackets,
+    }
+}
+
+/// Writes the comma between two elements, plus an empty line if `blank_line` is set.
+fn format_separator<W: LineWriter>(
+    out: &mut W,
+    blank_line: bool,
+    options: &FormatOptions,
+) -> fmt::Result {
+    out.write_char(',')?;
+    if options.compact {
+        if options.compact_spacing {
+            out.write_char(' ')?;
+        }
+    } else if blank_line {
+        out.new_line(0)?;
+    }
+    Ok(())
+}
+
+/// Starts a new line with the given indentation; does nothing in compact mode.
+fn format_line_break<W: LineWriter>(
+    out: &mut W,
+    indent: &Indent,
+    options: &FormatOptions,
+) -> fmt::Result {
+    if options.compact {
+        return Ok(());
+    }
+    out.new_line(indent.depth)?;
+    indent.write(out)
+}
+
+fn format_number<W: fmt::Write>(
+    out: &mut W,
+    n: &JsonNumber,
+    options: &FormatOptions,
+) -> fmt::Result {
+    match n {
+        JsonNumber::Float(n) if options.ecmascript_numbers => format_ecmascript_number(out, *n),
+        // Floats display without trailing zeros, except for the one after the
+        // point of an integral value, which `JsonNumber` adds.
+        JsonNumber::Float(n) if options.strip_trailing_zeros => write!(out, "{}", n),
+        n => write!(out, "{}", n),
+    }
+}
+
+/// Writes the formatted `number` with `separator` between each group of three
+/// digits of its integer part. The fraction and exponent are left as they are.
+fn format_grouped_digits<W: fmt::Write>(out: &mut W, number: &str, separator: char) -> fmt::Result {
+    let start = number
+        .find(|char: char| char.is_ascii_digit())
+        .unwrap_or(number.len());
+    let end = number[start..]
+        .find(|char: char| !char.is_ascii_digit())
+        .map_or(number.len(), |length| start + length);
+    out.write_str(&number[..start])?;
+    for (index, digit) in number[start..end].char_indices() {
+        if index > 0 && (end - start - index) % 3 == 0 {
+            out.write_char(separator)?;
+        }
+        out.write_char(digit)?;
+    }
+    out.write_str(&number[end..])
+}
+
+/// Writes `n` following the ECMAScript Number::toString algorithm: the shortest
+/// digits that round-trip, in plain notation for decimal exponents from -6 to 20
+/// and in exponent notation such as `1.5e-7` or `1e+21` otherwise.
+fn format_ecmascript_number<W: fmt::Write>(out: &mut W, n: f64) -> fmt::Result {
+    if n == 0.0 || !n.is_finite() {
+        // JSON.stringify writes -0 as 0, and NaN and infinities as null.
+        return out.write_str(if n == 0.0 { "0" } else { "null" });
+    }
+
+    // `{:e}` gives the shortest round-tripping digits, as in `1.2345e-7`.
+    let scientific = format!("{:e}", n.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // The value is 0.digits × 10^point.
+    let point = exponent.parse::<i32>().unwrap() + 1;
+
+    if n < 0.0 {
+        out.write_char('-')?;
+    }
+    if k <= point && point <= 21 {
+        write!(out, "{}{}", digits, "0".repeat((point - k) as usize))
+    } else if 0 < point && point <= 21 {
+        let (integer, fraction) = digits.split_at(point as usize);
+        write!(out, "{}.{}", integer, fraction)
+    } else if -6 < point && point <= 0 {
+        write!(out, "0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let sign = if point > 0 { '+' } else { '-' };
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            write!(out, "{}e{}{}", first, sign, (point - 1).abs())
+        } else {
+            write!(out, "{}.{}e{}{}", first, rest, sign, (point - 1).abs())
+        }
+    }
+}
+
+/// Starts the line of the element at `index` of an array, with the comma that
+/// separates it from the previous element placed as `array_comma_placement` says
+/// and the comment of `index_comments`. Returns the width of the line before the
+/// element.
+fn format_element_start<W: LineWriter>(
+    out: &mut W,
+    index: usize,
+    inner: &Indent,
+    options: &FormatOptions,
+) -> Result<usize, fmt::Error> {
+    let leading = options.array_comma_placement == CommaPlacement::Leading && !options.compact;
+    if index > 0 && !leading {
+        format_separator(out, false, options)?;
+    }
+    format_line_break(out, inner, options)?;
+    let mut column = inner.width(options);
+    if index > 0 && leading {
+        out.write_str(", ")?;
+        column += 2;
+    }
+    // Only the elements of the top-level array, whose lines are one level deep.
+    if options.index_comments && inner.depth == 1 {
+        let comment = format!("/* [{}] */ ", index);
+        out.write_str(&comment)?;
+        column += comment.len();
+    }
+    Ok(column)
 }
 
-fn format_value(value: &JsonValue, indent_level: usize) -> String {
-    match value {
-        JsonValue::Null => "null".to_string(),
-        JsonValue::Bool(b) => b.to_string(),
-        JsonValue::Number(n) => n.to_string(),
-        JsonValue::String(s) => format!("\"{}\"", s),
-        JsonValue::Object(_) => format_object(value, indent_level),
-        JsonValue::Array(_) => format_array(value, indent_level),
+/// Ends the last line inside a multi-line container, before its closing bracket.
+fn format_closing_line_break<W: LineWriter>(
+    out: &mut W,
+    indent: &Indent,
+    options: &FormatOptions,
+) -> fmt::Result {
+    if options.trailing_comma && !options.compact {
+        out.write_char(',')?;
+    }
+    format_line_break(out, indent, options)
+}
+
+pub(crate) fn format_string<W: fmt::Write>(
+    out: &mut W,
+    string: &str,
+    options: &FormatOptions,
+) -> fmt::Result {
+    let quote = options.quote.as_char();
+    out.write_char(quote)?;
+    let mut previous = None;
+    for char in string.chars() {
+        match char {
+            '\u{2028}' | '\u{2029}' if options.escape_js_unsafe => {
+                format_unicode_escape(out, char, options)?
+            }
+            '/' if options.escape_js_unsafe && previous == Some('<') => out.write_str("\\/")?,
+            _ if char == quote => write!(out, "\\{}", quote)?,
+            '\\' => out.write_str("\\\\")?,
+            '\u{0008}' => out.write_str("\\b")?,
+            '\u{000C}' => out.write_str("\\f")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{0000}'..='\u{001F}' => format_unicode_escape(out, char, options)?,
+            _ => out.write_char(char)?,
+        }
+        previous = Some(char);
+    }
+    out.write_char(quote)
+}
+
+fn format_unicode_escape<W: fmt::Write>(
+    out: &mut W,
+    char: char,
+    options: &FormatOptions,
+) -> fmt::Result {
+    match options.hex_case {
+        HexCase::Lower => write!(out, "\\u{:04x}", char as u32),
+        HexCase::Upper => write!(out, "\\u{:04X}", char as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parser, JsonNumber};
+    use crate::tokenizer::tokenize;
+
+    #[test]
+    fn format_null() {
+        let value = JsonValue::Null;
+        let result = format(&value);
+        assert_eq!(result, "null");
+    }
+
+    #[test]
+    fn format_true() {
+        let value = JsonValue::Bool(true);
+        let result = format(&value);
+        assert_eq!(result, "true");
+    }
+
+    #[test]
+    fn format_false() {
+        let value = JsonValue::Bool(false);
+        let result = format(&value);
+        assert_eq!(result, "false");
+    }
+
+    #[test]
+    fn format_number() {
+        let value = JsonValue::Number(JsonNumber::Float(123.4));
+        let result = format(&value);
+        assert_eq!(result, "123.4");
+    }
+
+    #[test]
+    fn format_string() {
+        let value = JsonValue::String("hello".to_string());
+        let result = format(&value);
+        assert_eq!(result, "\"hello\"");
+    }
+
+    #[test]
+    fn format_string_with_escaped_chars() {
+        let value = JsonValue::String(" \" \\ \u{0008} \u{000C} \n \r \t \u{001F}".to_string());
+        let result = format(&value);
+        assert_eq!(result, r#"" \" \\ \b \f \n \r \t \u001f""#);
+    }
+
+    #[test]
+    fn format_string_with_null_character_round_trips() {
+        let tokens = tokenize("\"a\\u0000b\"").unwrap();
+        let value = parser(&tokens).unwrap();
+        assert_eq!(value, JsonValue::String("a\u{0000}b".to_string()));
+
+        let result = format(&value);
+        assert_eq!(result, r#""a\u0000b""#);
+        assert_eq!(parser(&tokenize(&result).unwrap()), Ok(value));
+    }
+
+    #[test]
+    fn format_string_hex_case() {
+        let value = JsonValue::String("\u{001F}".to_string());
+        assert_eq!(format(&value), r#""\u001f""#);
+
+        let options = FormatOptions {
+            hex_case: HexCase::Upper,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format_with_options(&value, &options), r#""\u001F""#);
+    }
+
+    #[test]
+    fn format_string_escape_js_unsafe() {
+        let options = FormatOptions {
+            escape_js_unsafe: true,
+            ..FormatOptions::default()
+        };
+        let value = JsonValue::String("a\u{2028}b\u{2029}c".to_string());
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#""a\u2028b\u2029c""#
+        );
+        assert_eq!(format(&value), "\"a\u{2028}b\u{2029}c\"");
+
+        let value = JsonValue::String("</script> a/b".to_string());
+        assert_eq!(format_with_options(&value, &options), r#""<\/script> a/b""#);
+        assert_eq!(format(&value), r#""</script> a/b""#);
+    }
+
+    #[test]
+    fn format_object_empty() {
+        let value = JsonValue::Object(vec![]);
+        let result = format(&value);
+        assert_eq!(result, "{}");
+    }
+
+    #[test]
+    fn format_object_nested() {
+        let value = JsonValue::Object(vec![
+            (
+                "number".to_string(),
+                JsonValue::Number(JsonNumber::Float(123.4)),
+            ),
+            (
+                "object".to_string(),
+                JsonValue::Object(vec![
+                    ("string".to_string(), JsonValue::String("hello".to_string())),
+                    (
+                        "array".to_string(),
+                        JsonValue::Array(vec![
+                            JsonValue::Bool(true),
+                            JsonValue::Bool(false),
+                            JsonValue::Null,
+                        ]),
+                    ),
+                ]),
+            ),
+        ]);
+        let result = format(&value);
+        assert_eq!(
+            result,
+            r#"{
+  "number": 123.4,
+  "object": {
+    "string": "hello",
+    "array": [
+      true,
+      false,
+      null
+    ]
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn format_object_blank_line_between_top_level_entries() {
+        let value = JsonValue::Object(vec![
+            ("a".to_string(), JsonValue::Number(JsonNumber::Integer(1))),
+            (
+                "b".to_string(),
+                JsonValue::Object(vec![
+                    ("c".to_string(), JsonValue::Null),
+                    ("d".to_string(), JsonValue::Null),
+                ]),
+            ),
+            (
+                "e".to_string(),
+                JsonValue::Array(vec![JsonValue::Null, JsonValue::Null]),
+            ),
+        ]);
+        let options = FormatOptions {
+            blank_line_between_entries: true,
+            ..FormatOptions::default()
+        };
+        let result = format_with_options(&value, &options);
+        assert_eq!(
+            result,
+            r#"{
+  "a": 1,
+
+  "b": {
+    "c": null,
+    "d": null
+  },
+
+  "e": [
+    null,
+    null
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn format_kv_separator_after_escaped_key() {
+        let value = JsonValue::Object(vec![
+            (
+                "quote\"".to_string(),
+                JsonValue::Number(JsonNumber::Integer(1)),
+            ),
+            (
+                "slash\\".to_string(),
+                JsonValue::Array(vec![JsonValue::Null]),
+            ),
+        ]);
+        let options = FormatOptions {
+            kv_separator: Some(" : ".to_string()),
+            inline_array_width: Some(80),
+            ..FormatOptions::default()
+        };
+        let result = format_with_options(&value, &options);
+        assert_eq!(
+            result,
+            r#"{
+  "quote\"" : 1,
+  "slash\\" : [null]
+}"#
+        );
+        assert_eq!(parser(&tokenize(&result).unwrap()), Ok(value));
+    }
+
+    #[test]
+    fn format_array_indent() {
+        let value = JsonValue::Object(vec![(
+            "a".to_string(),
+            JsonValue::Array(vec![
+                JsonValue::Number(JsonNumber::Integer(1)),
+                JsonValue::Object(vec![("b".to_string(), JsonValue::Null)]),
+            ]),
+        )]);
+        let options = FormatOptions {
+            array_indent: Some("    ".to_string()),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#"{
+  "a": [
+      1,
+      {
+        "b": null
+      }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn format_compact() {
+        let value = JsonValue::Object(vec![
+            (
+                "a".to_string(),
+                JsonValue::Array(vec![
+                    JsonValue::Number(JsonNumber::Integer(1)),
+                    JsonValue::Number(JsonNumber::Integer(2)),
+                ]),
+            ),
+            ("b".to_string(), JsonValue::Object(vec![])),
+            ("c".to_string(), JsonValue::Array(vec![])),
+        ]);
+        let options = FormatOptions {
+            compact: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#"{"a":[1,2],"b":{},"c":[]}"#
+        );
+    }
+
+    #[test]
+    fn format_compact_spacing() {
+        let value = JsonValue::Object(vec![
+            (
+                "a".to_string(),
+                JsonValue::Array(vec![
+                    JsonValue::Number(JsonNumber::Integer(1)),
+                    JsonValue::Number(JsonNumber::Integer(2)),
+                ]),
+            ),
+            ("b".to_string(), JsonValue::Object(vec![])),
+            ("c".to_string(), JsonValue::Array(vec![])),
+        ]);
+        let options = FormatOptions {
+            compact: true,
+            compact_spacing: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#"{"a":[1, 2], "b":{ }, "c":[ ]}"#
+        );
+    }
+
+    #[test]
+    fn format_inline_array_width() {
+        let value = JsonValue::Object(vec![
+            (
+                "a".to_string(),
+                JsonValue::Array(vec![
+                    JsonValue::Number(JsonNumber::Integer(1)),
+                    JsonValue::Number(JsonNumber::Integer(2)),
+                ]),
+            ),
+            (
+                "b".to_string(),
+                JsonValue::Array(vec![JsonValue::Array(vec![JsonValue::Null])]),
+            ),
+        ]);
+        let options = FormatOptions {
+            inline_array_width: Some(13),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            "{\n  \"a\": [1, 2],\n  \"b\": [\n    [null]\n  ]\n}"
+        );
+
+        let options = FormatOptions {
+            inline_array_width: Some(12),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": [\n    [null]\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn format_inline_array_tab_width() {
+        let value = JsonValue::Object(vec![(
+            "key".to_string(),
+            JsonValue::Array(vec![
+                JsonValue::Number(JsonNumber::Integer(1)),
+                JsonValue::Number(JsonNumber::Integer(2)),
+            ]),
+        )]);
+        let options = FormatOptions {
+            indent: "\t".to_string(),
+            inline_array_width: Some(20),
+            tab_width: 4,
+            ..FormatOptions::default()
+        };
+        // 4 (tab) + 5 ("key") + 2 (": ") + 6 ("[1, 2]") = 17 columns
+        assert_eq!(
+            format_with_options(&value, &options),
+            "{\n\t\"key\": [1, 2]\n}"
+        );
+
+        let options = FormatOptions {
+            tab_width: 8,
+            ..options
+        };
+        // 8 (tab) + 5 + 2 + 6 = 21 columns
+        assert_eq!(
+            format_with_options(&value, &options),
+            "{\n\t\"key\": [\n\t\t1,\n\t\t2\n\t]\n}"
+        );
+    }
+
+    #[test]
+    fn format_max_line_width_small_object_fits() {
+        let value = JsonValue::Object(vec![
+            (
+                "a".to_string(),
+                JsonValue::Object(vec![
+                    ("x".to_string(), JsonValue::Number(JsonNumber::Integer(1))),
+                    (
+                        "y".to_string(),
+                        JsonValue::Array(vec![JsonValue::Bool(true)]),
+                    ),
+                ]),
+            ),
+            ("b".to_string(), JsonValue::Object(vec![])),
+        ]);
+        let options = FormatOptions {
+            max_line_width: Some(40),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#"{"a": {"x": 1, "y": [true]}, "b": {}}"#
+        );
+    }
+
+    #[test]
+    fn format_max_line_width_large_object_breaks() {
+        let value = JsonValue::Object(vec![
+            (
+                "a".to_string(),
+                JsonValue::Object(vec![
+                    ("x".to_string(), JsonValue::Number(JsonNumber::Integer(1))),
+                    (
+                        "y".to_string(),
+                        JsonValue::Array(vec![JsonValue::Bool(true)]),
+                    ),
+                ]),
+            ),
+            (
+                "long".to_string(),
+                JsonValue::String("a value that does not fit".to_string()),
+            ),
+        ]);
+        let options = FormatOptions {
+            max_line_width: Some(30),
+            ..FormatOptions::default()
+        };
+        // `  "a": {"x": 1, "y": [true]}` is exactly 30 columns wide.
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#"{
+  "a": {"x": 1, "y": [true]},
+  "long": "a value that does not fit"
+}"#
+        );
+    }
+
+    #[test]
+    fn format_table_arrays() {
+        let value = JsonValue::Object(vec![(
+            "people".to_string(),
+            JsonValue::Array(vec![
+                JsonValue::Object(vec![
+                    ("name".to_string(), JsonValue::String("Alice".to_string())),
+                    (
+                        "age".to_string(),
+                        JsonValue::Number(JsonNumber::Integer(30)),
+                    ),
+                    ("admin".to_string(), JsonValue::Bool(true)),
+                ]),
+                JsonValue::Object(vec![
+                    ("name".to_string(), JsonValue::String("Bob".to_string())),
+                    ("age".to_string(), JsonValue::Number(JsonNumber::Integer(4))),
+                    ("admin".to_string(), JsonValue::Null),
+                ]),
+            ]),
+        )]);
+        let options = FormatOptions {
+            table_arrays: true,
+            ..FormatOptions::default()
+        };
+        let result = format_with_options(&value, &options);
+        assert_eq!(
+            result,
+            r#"{
+  "people": [
+    {"name": "Alice", "age": 30, "admin": true},
+    {"name": "Bob",   "age": 4,  "admin": null}
+  ]
+}"#
+        );
+        assert_eq!(parser(&tokenize(&result).unwrap()), Ok(value));
+    }
+
+    #[test]
+    fn format_array_items_per_line() {
+        let squares = (1..=20).map(|n| JsonValue::Number(JsonNumber::Integer(n * n)));
+        let value = JsonValue::Object(vec![(
+            "squares".to_string(),
+            JsonValue::Array(squares.collect()),
+        )]);
+        let options = FormatOptions {
+            array_items_per_line: Some(8),
+            ..FormatOptions::default()
+        };
+        let result = format_with_options(&value, &options);
+        assert_eq!(
+            result,
+            r#"{
+  "squares": [
+    1,   4,   9,   16,  25,  36,  49,  64,
+    81,  100, 121, 144, 169, 196, 225, 256,
+    289, 324, 361, 400
+  ]
+}"#
+        );
+        assert_eq!(parser(&tokenize(&result).unwrap()), Ok(value));
+
+        // Arrays containing containers are written one element per line.
+        let nested = parser(&tokenize("[1, [2]]").unwrap()).unwrap();
+        assert_eq!(
+            format_with_options(&nested, &options),
+            "[\n  1,\n  [\n    2\n  ]\n]"
+        );
+    }
+
+    #[test]
+    fn format_table_arrays_sorts_keys() {
+        let value = parser(&tokenize(r#"[{"b": 1, "a": 2}, {"a": 3, "b": 40}]"#).unwrap()).unwrap();
+        let options = FormatOptions {
+            table_arrays: true,
+            sort_keys: Some(KeyOrder::CodePoint),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            "[\n  {\"a\": 2, \"b\": 1},\n  {\"a\": 3, \"b\": 40}\n]"
+        );
+
+        // Rows outside `sort_depth` keep their document order.
+        let options = FormatOptions {
+            sort_depth: Some(0..=0),
+            ..options
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            format_with_options(
+                &value,
+                &FormatOptions {
+                    sort_keys: None,
+                    ..options.clone()
+                }
+            )
+        );
     }
-}
 
-fn format_object(value: &JsonValue, indent_level: usize) -> String {
-    if let JsonValue::Object(entries) = value {
-        if entries.len() == 0 {
-            return "{}".to_string();
-        }
+    #[test]
+    fn format_table_arrays_elided_rows() {
+        let value = parser(&tokenize(r#"[{"a": 1, "b": 2, "c": 3}]"#).unwrap()).unwrap();
+        let options = FormatOptions {
+            table_arrays: true,
+            max_object_entries: Some(1),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            format_with_options(
+                &value,
+                &FormatOptions {
+                    table_arrays: false,
+                    ..options.clone()
+                }
+            )
+        );
+        assert!(format_with_options(&value, &options).contains("... (2 more)"));
+    }
 
-        let entries_string = entries
-            .iter()
-            .map(|(key, value)| {
-                format!(
-                    "{}\"{}\": {}",
-                    "  ".repeat(indent_level),
-                    key,
-                    format_value(value, indent_level + 1)
-                )
-            })
-            .collect::<Vec<String>>()
-            .join(",\n");
-
-        format!(
-            "{{\n{}\n{}}}",
-            entries_string,
-            "  ".repeat(indent_level - 1)
-        )
-    } else {
-        panic!("Expected object");
+    #[test]
+    fn format_table_arrays_heterogeneous_keys() {
+        let value = JsonValue::Array(vec![
+            JsonValue::Object(vec![(
+                "a".to_string(),
+                JsonValue::Number(JsonNumber::Integer(1)),
+            )]),
+            JsonValue::Object(vec![(
+                "b".to_string(),
+                JsonValue::Number(JsonNumber::Integer(2)),
+            )]),
+        ]);
+        let options = FormatOptions {
+            table_arrays: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format_with_options(&value, &options), format(&value));
     }
-}
 
-fn format_array(value: &JsonValue, indent_level: usize) -> String {
-    if let JsonValue::Array(values) = value {
-        if values.len() == 0 {
-            return "[]".to_string();
-        }
+    #[test]
+    fn write_ndjson_one_value_per_line() {
+        let values = vec![
+            JsonValue::Object(vec![
+                ("a".to_string(), JsonValue::Number(JsonNumber::Integer(1))),
+                ("b".to_string(), JsonValue::Array(vec![JsonValue::Null])),
+            ]),
+            JsonValue::String("line\nbreak".to_string()),
+            JsonValue::Array(vec![]),
+        ];
+        let mut output = Vec::new();
+        write_ndjson(&mut output, values.into_iter(), &FormatOptions::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"a\":1,\"b\":[null]}\n\"line\\nbreak\"\n[]\n"
+        );
+    }
 
-        let values_string = values
-            .iter()
-            .map(|value| {
-                format!(
-                    "{}{}",
-                    "  ".repeat(indent_level),
-                    format_value(value, indent_level + 1)
-                )
-            })
-            .collect::<Vec<String>>()
-            .join(",\n");
-
-        format!("[\n{}\n{}]", values_string, "  ".repeat(indent_level - 1))
-    } else {
-        panic!("Expected array");
+    #[test]
+    fn format_with_writer_line_numbers() {
+        use std::fmt::Write;
+
+        let value = JsonValue::Object(vec![
+            ("a".to_string(), JsonValue::Number(JsonNumber::Integer(1))),
+            ("b".to_string(), JsonValue::Array(vec![JsonValue::Null])),
+        ]);
+        let mut output = String::new();
+        let mut line = 0;
+        format_with_writer(
+            &mut output,
+            &value,
+            &FormatOptions::default(),
+            |out, depth| {
+                line += 1;
+                write!(out, "{} [{}] ", line, depth)
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            r#"1 [0] {
+2 [1]   "a": 1,
+3 [1]   "b": [
+4 [2]     null
+5 [1]   ]
+6 [0] }"#
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn format_shallow() {
+        let options = FormatOptions {
+            shallow: true,
+            ..FormatOptions::default()
+        };
+        let format_shallow = |content: &str| {
+            format_with_options(&parser(&tokenize(content).unwrap()).unwrap(), &options)
+        };
+        assert_eq!(
+            format_shallow(r#"{"a": {"x": 1}, "b": [1, 2], "c": "d", "e": {}}"#),
+            "{\n  \"a\": {\"x\":1},\n  \"b\": [1,2],\n  \"c\": \"d\",\n  \"e\": {}\n}"
+        );
+        assert_eq!(
+            format_shallow(r#"[{"a": [1, {"b": null}]}, []]"#),
+            "[\n  {\"a\":[1,{\"b\":null}]},\n  []\n]"
+        );
+        assert_eq!(format_shallow("1"), "1");
+    }
 
     #[test]
-    fn format_null() {
-        let value = JsonValue::Null;
-        let result = format(&value);
-        assert_eq!(result, "null");
+    fn format_thousands_separator() {
+        let options = FormatOptions {
+            thousands_separator: Some(','),
+            ..FormatOptions::single_line()
+        };
+        let value = parser(&tokenize("[1000000, -1234567.891, 999, 1000, 0.5]").unwrap()).unwrap();
+        assert_eq!(
+            format_with_options(&value, &options),
+            "[1,000,000, -1,234,567.891, 999, 1,000, 0.5]"
+        );
+        assert_eq!(
+            value.serialized_len(&options),
+            format_with_options(&value, &options).len()
+        );
     }
 
     #[test]
-    fn format_true() {
-        let value = JsonValue::Bool(true);
-        let result = format(&value);
-        assert_eq!(result, "true");
+    fn format_quote_styles() {
+        let value = parser(&tokenize(r#"{"it's": ["say \"hi\"", "a'b\"c\\"]}"#).unwrap()).unwrap();
+        assert_eq!(
+            format_with_options(&value, &FormatOptions::single_line()),
+            r#"{"it's": ["say \"hi\"", "a'b\"c\\"]}"#
+        );
+        let single = FormatOptions {
+            quote: QuoteStyle::Single,
+            ..FormatOptions::single_line()
+        };
+        assert_eq!(
+            format_with_options(&value, &single),
+            r#"{'it\'s': ['say "hi"', 'a\'b"c\\']}"#
+        );
+
+        assert_eq!(QuoteStyle::from_char('"'), Some(QuoteStyle::Double));
+        assert_eq!(QuoteStyle::from_char('\''), Some(QuoteStyle::Single));
+        assert_eq!(QuoteStyle::from_char('`'), None);
     }
 
     #[test]
-    fn format_false() {
-        let value = JsonValue::Bool(false);
-        let result = format(&value);
-        assert_eq!(result, "false");
+    fn format_index_comments() {
+        let value = parser(&tokenize(r#"[{"a": [1]}, "b", [null]]"#).unwrap()).unwrap();
+        let options = FormatOptions {
+            index_comments: true,
+            ..FormatOptions::default()
+        };
+        let expected = r#"[
+  /* [0] */ {
+    "a": [
+      1
+    ]
+  },
+  /* [1] */ "b",
+  /* [2] */ [
+    null
+  ]
+]"#;
+        assert_eq!(format_with_options(&value, &options), expected);
+
+        let JsonValue::Array(values) = value.clone() else {
+            unreachable!()
+        };
+        let mut streamed = Vec::new();
+        format_array_from_iter(&mut streamed, values.into_iter(), &options).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        let nested = parser(&tokenize(r#"{"a": [1]}"#).unwrap()).unwrap();
+        assert_eq!(format_with_options(&nested, &options), format(&nested));
     }
 
     #[test]
-    fn format_number() {
-        let value = JsonValue::Number(123.4);
-        let result = format(&value);
-        assert_eq!(result, "123.4");
+    fn format_strip_trailing_zeros() {
+        let value = parser(&tokenize("[1.2300, 5.0, 5, -0.0, 1e2, 3.1400e1]").unwrap()).unwrap();
+        assert_eq!(
+            format_with_options(&value, &FormatOptions::single_line()),
+            "[1.23, 5.0, 5, -0.0, 100.0, 31.4]"
+        );
+        let options = FormatOptions {
+            strip_trailing_zeros: true,
+            ..FormatOptions::single_line()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            "[1.23, 5, 5, -0, 100, 31.4]"
+        );
     }
 
     #[test]
-    fn format_string() {
-        let value = JsonValue::String("hello".to_string());
-        let result = format(&value);
-        assert_eq!(result, "\"hello\"");
+    fn format_ecmascript_numbers() {
+        let options = FormatOptions {
+            ecmascript_numbers: true,
+            ..FormatOptions::default()
+        };
+        let format_number =
+            |n: f64| format_with_options(&JsonValue::Number(JsonNumber::Float(n)), &options);
+
+        // Expected values are those of `String(n)` in JavaScript.
+        let cases = [
+            (0.1, "0.1"),
+            (100.0, "100"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (1.5e300, "1.5e+300"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.2345e-10, "-1.2345e-10"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (-2.5, "-2.5"),
+            (-0.0, "0"),
+            (5e-324, "5e-324"),
+        ];
+        for (n, expected) in cases {
+            assert_eq!(format_number(n), expected, "formatting {:e}", n);
+        }
+        assert_eq!(
+            format_with_options(&JsonValue::Number(JsonNumber::Integer(100)), &options),
+            "100"
+        );
     }
 
     #[test]
-    fn format_object_empty() {
-        let value = JsonValue::Object(vec![]);
-        let result = format(&value);
-        assert_eq!(result, "{}");
+    fn format_emit_bom() {
+        let value = parser(&tokenize(r#"{"a": ["b"]}"#).unwrap()).unwrap();
+        let options = FormatOptions {
+            emit_bom: true,
+            ..FormatOptions::default()
+        };
+        let formatted = format_with_options(&value, &options);
+        assert_eq!(
+            formatted.strip_prefix('\u{FEFF}'),
+            Some(format(&value).as_str())
+        );
+        assert_eq!(formatted.matches('\u{FEFF}').count(), 1);
+        assert_eq!(value.serialized_len(&options), formatted.len());
+
+        let mut buf = String::new();
+        format_into(&value, &mut buf, &options);
+        format_into(&value, &mut buf, &options);
+        assert_eq!(buf, formatted);
     }
 
     #[test]
-    fn format_object_nested() {
-        let value = JsonValue::Object(vec![
-            ("number".to_string(), JsonValue::Number(123.4)),
-            (
-                "object".to_string(),
-                JsonValue::Object(vec![
-                    ("string".to_string(), JsonValue::String("hello".to_string())),
-                    (
-                        "array".to_string(),
-                        JsonValue::Array(vec![
-                            JsonValue::Bool(true),
-                            JsonValue::Bool(false),
-                            JsonValue::Null,
-                        ]),
-                    ),
-                ]),
-            ),
-        ]);
-        let result = format(&value);
+    fn format_array_comma_placement() {
+        let value = parser(&tokenize(r#"{"a": [1, [2, 3], {"b": null}]}"#).unwrap()).unwrap();
         assert_eq!(
-            result,
+            format(&value),
             r#"{
-  "number": 123.4,
-  "object": {
-    "string": "hello",
-    "array": [
-      true,
-      false,
-      null
+  "a": [
+    1,
+    [
+      2,
+      3
+    ],
+    {
+      "b": null
+    }
+  ]
+}"#
+        );
+
+        let options = FormatOptions {
+            array_comma_placement: CommaPlacement::Leading,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#"{
+  "a": [
+    1
+    , [
+      2
+      , 3
     ]
-  }
+    , {
+      "b": null
+    }
+  ]
+}"#
+        );
+
+        let array = parser(&tokenize("[1, [2, 3]]").unwrap()).unwrap();
+        let JsonValue::Array(values) = array.clone() else {
+            unreachable!()
+        };
+        let mut streamed = Vec::new();
+        format_array_from_iter(&mut streamed, values.into_iter(), &options).unwrap();
+        assert_eq!(
+            String::from_utf8(streamed).unwrap(),
+            format_with_options(&array, &options)
+        );
+
+        let compact = FormatOptions {
+            compact: true,
+            ..options
+        };
+        assert_eq!(
+            format_with_options(&value, &compact),
+            r#"{"a":[1,[2,3],{"b":null}]}"#
+        );
+    }
+
+    #[test]
+    fn format_into_slice_fits() {
+        let value = parser(&tokenize(r#"{"a": ["é", null]}"#).unwrap()).unwrap();
+        let formatted = format(&value);
+        let mut buf = [0u8; 64];
+        let len = format_into_slice(&value, &mut buf, &FormatOptions::default()).unwrap();
+        assert_eq!(len, formatted.len());
+        assert_eq!(&buf[..len], formatted.as_bytes());
+
+        let mut exact = vec![0u8; formatted.len()];
+        assert_eq!(
+            format_into_slice(&value, &mut exact, &FormatOptions::default()),
+            Ok(formatted.len())
+        );
+    }
+
+    #[test]
+    fn format_into_slice_buffer_full() {
+        let value = parser(&tokenize(r#"{"a": ["é", null]}"#).unwrap()).unwrap();
+        let mut buf = vec![0u8; format(&value).len() - 1];
+        assert_eq!(
+            format_into_slice(&value, &mut buf, &FormatOptions::default()),
+            Err(BufferFull)
+        );
+        assert_eq!(
+            format_into_slice(&JsonValue::Null, &mut [], &FormatOptions::default()),
+            Err(BufferFull)
+        );
+    }
+
+    #[test]
+    fn formatted_cache_reuses_output() {
+        let mut value = parser(&tokenize(r#"{"a": [1, 2]}"#).unwrap()).unwrap();
+        let options = FormatOptions::default();
+        let mut cache = FormattedCache::new();
+
+        let first = cache.format(&value, &options).as_ptr();
+        let second = cache.format(&value, &options);
+        assert_eq!(second.as_ptr(), first);
+        assert_eq!(second, format(&value));
+
+        let JsonValue::Object(entries) = &mut value else {
+            unreachable!()
+        };
+        entries[0].1 = JsonValue::Null;
+        assert_eq!(cache.format(&value, &options), "{\n  \"a\": null\n}");
+
+        let compact = FormatOptions {
+            compact: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(cache.format(&value, &compact), r#"{"a":null}"#);
+    }
+
+    #[test]
+    fn format_single_line() {
+        let value = parser(
+            &tokenize(r#"{"a": 1, "b": [1, 2, {"c": null}], "d": {"e": "f"}, "g": []}"#).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            format_with_options(&value, &FormatOptions::single_line()),
+            r#"{"a": 1, "b": [1, 2, {"c": null}], "d": {"e": "f"}, "g": [ ]}"#
+        );
+    }
+
+    #[test]
+    fn format_trailing_comma() {
+        let value =
+            parser(&tokenize(r#"{"a": [1, 2], "b": {}, "c": [], "d": {"e": null}}"#).unwrap())
+                .unwrap();
+        let options = FormatOptions {
+            trailing_comma: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            r#"{
+  "a": [
+    1,
+    2,
+  ],
+  "b": {},
+  "c": [],
+  "d": {
+    "e": null,
+  },
 }"#
         );
+
+        let inline = FormatOptions {
+            max_line_width: Some(80),
+            ..options.clone()
+        };
+        assert_eq!(
+            format_with_options(&value, &inline),
+            r#"{"a": [1, 2], "b": {}, "c": [], "d": {"e": null}}"#
+        );
+        let compact = FormatOptions {
+            compact: true,
+            ..options
+        };
+        assert_eq!(
+            format_with_options(&value, &compact),
+            r#"{"a":[1,2],"b":{},"c":[],"d":{"e":null}}"#
+        );
+    }
+
+    #[test]
+    fn format_into_reused_buffer() {
+        let options = FormatOptions::default();
+        let mut buf = String::with_capacity(64);
+        let capacity = buf.capacity();
+        for content in [r#"{"a": [1, 2]}"#, "null", r#"["x"]"#] {
+            let value = parser(&tokenize(content).unwrap()).unwrap();
+            format_into(&value, &mut buf, &options);
+            assert_eq!(buf, format_with_options(&value, &options));
+        }
+        assert_eq!(buf, "[\n  \"x\"\n]");
+        assert_eq!(buf.capacity(), capacity);
+    }
+
+    #[test]
+    fn format_sort_scalar_arrays() {
+        let options = FormatOptions {
+            sort_scalar_arrays: true,
+            max_line_width: Some(80),
+            ..FormatOptions::default()
+        };
+        let format_sorted = |content: &str| {
+            format_with_options(&parser(&tokenize(content).unwrap()).unwrap(), &options)
+        };
+
+        assert_eq!(
+            format_sorted(r#"{"tags": ["web", "api", "Zeta", "beta"]}"#),
+            r#"{"tags": ["Zeta", "api", "beta", "web"]}"#
+        );
+        assert_eq!(format_sorted("[10, -2.5, 3, 0]"), "[-2.5, 0, 3, 10]");
+        assert_eq!(
+            format_sorted(r#"[3, "b", 1, "a", true]"#),
+            r#"[3, "b", 1, "a", true]"#
+        );
+        assert_eq!(format_sorted("[[2, 1], {}]"), "[[1, 2], {}]");
+    }
+
+    #[test]
+    fn format_sort_keys_stable() {
+        let format_sorted = |content: &str, order: KeyOrder| {
+            let options = FormatOptions {
+                sort_keys: Some(order),
+                max_line_width: Some(80),
+                ..FormatOptions::default()
+            };
+            format_with_options(&parser(&tokenize(content).unwrap()).unwrap(), &options)
+        };
+
+        let content = r#"{"b": 1, "a": 2, "B": 3, "A": 4}"#;
+        assert_eq!(
+            format_sorted(content, KeyOrder::CodePoint),
+            r#"{"A": 4, "B": 3, "a": 2, "b": 1}"#
+        );
+        // `a` and `A` tie, as do `b` and `B`, and each pair keeps its input order.
+        assert_eq!(
+            format_sorted(content, KeyOrder::CaseInsensitive),
+            r#"{"a": 2, "A": 4, "b": 1, "B": 3}"#
+        );
+        assert_eq!(
+            format_sorted(r#"{"x": {"A": 1, "a": 2}}"#, KeyOrder::CaseInsensitive),
+            r#"{"x": {"A": 1, "a": 2}}"#
+        );
+    }
+
+    #[test]
+    fn format_sort_depth() {
+        let value =
+            parser(&tokenize(r#"{"b": {"y": 1, "x": 2}, "a": [{"d": 1, "c": 2}]}"#).unwrap())
+                .unwrap();
+        let format_at = |depths: RangeInclusive<usize>, max_line_width: Option<usize>| {
+            let options = FormatOptions {
+                sort_keys: Some(KeyOrder::CodePoint),
+                sort_depth: Some(depths),
+                max_line_width,
+                ..FormatOptions::single_line()
+            };
+            format_with_options(&value, &options)
+        };
+
+        assert_eq!(
+            format_at(0..=0, None),
+            r#"{"a": [{"d": 1, "c": 2}], "b": {"y": 1, "x": 2}}"#
+        );
+        assert_eq!(
+            format_at(1..=1, None),
+            r#"{"b": {"x": 2, "y": 1}, "a": [{"d": 1, "c": 2}]}"#
+        );
+        assert_eq!(
+            format_at(1..=2, None),
+            r#"{"b": {"x": 2, "y": 1}, "a": [{"c": 2, "d": 1}]}"#
+        );
+        // Objects written inline within a multi-line document count depth alike.
+        let options = FormatOptions {
+            sort_keys: Some(KeyOrder::CodePoint),
+            sort_depth: Some(1..=1),
+            max_line_width: Some(30),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            "{\n  \"b\": {\"x\": 2, \"y\": 1},\n  \"a\": [{\"d\": 1, \"c\": 2}]\n}"
+        );
+    }
+
+    #[test]
+    fn format_max_array_elements() {
+        let value = parser(&tokenize("[1, 2, 3, 4, 5]").unwrap()).unwrap();
+        let options = FormatOptions {
+            max_array_elements: Some(2),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            "[\n  1,\n  2,\n  ... (3 more)\n]"
+        );
+
+        let options = FormatOptions {
+            max_array_elements: Some(5),
+            ..options
+        };
+        assert_eq!(format_with_options(&value, &options), format(&value));
+
+        let options = FormatOptions {
+            max_array_elements: Some(1),
+            max_line_width: Some(80),
+            ..FormatOptions::default()
+        };
+        assert_eq!(format_with_options(&value, &options), "[1, ... (4 more)]");
+    }
+
+    #[test]
+    fn format_max_object_entries() {
+        let value = parser(&tokenize(r#"{"a": 1, "b": 2, "c": 3}"#).unwrap()).unwrap();
+        let options = FormatOptions {
+            max_object_entries: Some(1),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            "{\n  \"a\": 1,\n  ... (2 more)\n}"
+        );
     }
 
     #[test]
@@ -162,38 +2311,56 @@ mod tests {
         let value = JsonValue::Array(vec![
             JsonValue::String("hello".to_string()),
             JsonValue::Object(vec![
-                ("age".to_string(), JsonValue::Number(18.0)),
+                (
+                    "age".to_string(),
+                    JsonValue::Number(JsonNumber::Integer(18)),
+                ),
                 ("name".to_string(), JsonValue::String("Alice".to_string())),
                 (
                     "hobbies".to_string(),
                     JsonValue::Array(vec![
                         JsonValue::Object(vec![
                             ("name".to_string(), JsonValue::String("Reading".to_string())),
-                            ("level".to_string(), JsonValue::Number(3.0)),
+                            (
+                                "level".to_string(),
+                                JsonValue::Number(JsonNumber::Integer(3)),
+                            ),
                         ]),
                         JsonValue::Object(vec![
                             (
                                 "name".to_string(),
                                 JsonValue::String("Swimming".to_string()),
                             ),
-                            ("level".to_string(), JsonValue::Number(2.0)),
+                            (
+                                "level".to_string(),
+                                JsonValue::Number(JsonNumber::Integer(2)),
+                            ),
                         ]),
                     ]),
                 ),
             ]),
             JsonValue::Object(vec![
-                ("age".to_string(), JsonValue::Number(24.0)),
+                (
+                    "age".to_string(),
+                    JsonValue::Number(JsonNumber::Integer(24)),
+                ),
                 ("name".to_string(), JsonValue::String("Bob".to_string())),
                 (
                     "hobbies".to_string(),
                     JsonValue::Array(vec![
                         JsonValue::Object(vec![
                             ("name".to_string(), JsonValue::String("Running".to_string())),
-                            ("level".to_string(), JsonValue::Number(1.0)),
+                            (
+                                "level".to_string(),
+                                JsonValue::Number(JsonNumber::Integer(1)),
+                            ),
                         ]),
                         JsonValue::Object(vec![
                             ("name".to_string(), JsonValue::String("Cycling".to_string())),
-                            ("level".to_string(), JsonValue::Number(2.0)),
+                            (
+                                "level".to_string(),
+                                JsonValue::Number(JsonNumber::Integer(2)),
+                            ),
                         ]),
                     ]),
                 ),
@@ -235,4 +2402,121 @@ mod tests {
 ]"#
         );
     }
+
+    #[test]
+    fn format_array_from_iter_matches_batch_format() {
+        let values = vec![
+            JsonValue::Number(JsonNumber::Integer(1)),
+            JsonValue::String("two".to_string()),
+            JsonValue::Object(vec![("three".to_string(), JsonValue::Bool(true))]),
+        ];
+        let options = FormatOptions::default();
+        let mut buffer: Vec<u8> = Vec::new();
+        format_array_from_iter(&mut buffer, values.clone().into_iter(), &options).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            format_with_options(&JsonValue::Array(values), &options)
+        );
+    }
+
+    #[test]
+    fn format_array_from_iter_compact() {
+        let values = vec![JsonValue::Null, JsonValue::Array(vec![])];
+        let options = FormatOptions {
+            compact: true,
+            compact_spacing: true,
+            ..FormatOptions::default()
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+        format_array_from_iter(&mut buffer, values.into_iter(), &options).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "[null, [ ]]");
+    }
+
+    #[test]
+    fn format_array_from_iter_empty() {
+        let mut buffer: Vec<u8> = Vec::new();
+        format_array_from_iter(&mut buffer, std::iter::empty(), &FormatOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "[]");
+    }
+
+    #[test]
+    fn serialized_len_matches_formatted_len() {
+        let documents = [
+            "null",
+            "-12.5e3",
+            r#""esc\"aped\n \u0001 ünïcode""#,
+            "[]",
+            r#"{"a": [1, {"b": null}], "c": {}, "d": "\u00e9"}"#,
+        ];
+        let blank_lines = FormatOptions {
+            indent: "\t".to_string(),
+            blank_line_between_entries: true,
+            ..FormatOptions::default()
+        };
+        for options in [FormatOptions::default(), blank_lines] {
+            for document in documents {
+                let value = parser(&tokenize(document).unwrap()).unwrap();
+                assert_eq!(
+                    value.serialized_len(&options),
+                    format_with_options(&value, &options).len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn format_deep_nesting_without_recursion() {
+        let compact = FormatOptions {
+            compact: true,
+            ..FormatOptions::default()
+        };
+        let depth = 200_000;
+        let content = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let value = crate::parse(&content).unwrap();
+        assert_eq!(format_with_options(&value, &compact), content);
+
+        // Past MAX_LAYOUT_DEPTH the rest of the value is written on one line.
+        let pretty = format(&value);
+        assert_eq!(pretty.lines().count(), 2 * MAX_LAYOUT_DEPTH + 1);
+        let stripped: String = pretty
+            .chars()
+            .filter(|char| !char.is_whitespace())
+            .collect();
+        assert_eq!(stripped, content);
+        value.drop_deep();
+
+        let content = format!(
+            "{}[1,{{\"b\":\"x\"}},[],{{}}]{}",
+            r#"{"a":"#.repeat(200),
+            "}".repeat(200)
+        );
+        let value = crate::parse(&content).unwrap();
+        assert_eq!(format_with_options(&value, &compact), content);
+    }
+
+    #[test]
+    fn format_deep_nesting_sorts_and_elides() {
+        let nested = |inner: &str| format!("{}{}{}", "[".repeat(200), inner, "]".repeat(200));
+        let value = crate::parse(&nested(r#"{"b": [3, 1, 2], "a": {}, "c": null}"#)).unwrap();
+        let options = FormatOptions {
+            sort_keys: Some(KeyOrder::CodePoint),
+            sort_scalar_arrays: true,
+            max_array_elements: Some(2),
+            compact: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_with_options(&value, &options),
+            nested(r#"{"a":{},"b":[1,2,... (1 more)],"c":null}"#)
+        );
+
+        let options = FormatOptions {
+            sort_keys: Some(KeyOrder::CodePoint),
+            sort_depth: Some(0..=199),
+            max_object_entries: Some(1),
+            ..FormatOptions::default()
+        };
+        let formatted = format_with_options(&value, &options);
+        assert!(formatted.contains(r#"{"b":[3,1,2],... (2 more)}"#));
+    }
 }
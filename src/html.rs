@@ -0,0 +1,118 @@
+use crate::formatter::{self, FormatOptions};
+use crate::parser::JsonValue;
+
+/// Formats `value` as HTML for syntax highlighting, with each key and scalar
+/// wrapped in a `<span>` whose class is `json-key`, `json-string`, `json-number`,
+/// `json-bool` or `json-null`.
+///
+/// The layout matches `formatter::format`, using newlines and spaces, so the
+/// result is meant to be placed in a `<pre>` element.
+pub fn format_html(value: &JsonValue) -> String {
+    let mut out = String::new();
+    format_value(&mut out, value, 0, &FormatOptions::default());
+    out
+}
+
+fn format_value(out: &mut String, value: &JsonValue, depth: usize, options: &FormatOptions) {
+    match value {
+        JsonValue::Null => format_span(out, "json-null", "null"),
+        JsonValue::Bool(b) => format_span(out, "json-bool", &b.to_string()),
+        JsonValue::Number(_) => format_span(
+            out,
+            "json-number",
+            &formatter::format_fragment(value, options),
+        ),
+        JsonValue::String(s) => format_span(out, "json-string", &quote(s, options)),
+        JsonValue::Array(values) if !values.is_empty() => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                format_line_break(out, depth + 1, options);
+                format_value(out, value, depth + 1, options);
+            }
+            format_line_break(out, depth, options);
+            out.push(']');
+        }
+        JsonValue::Object(entries) if !entries.is_empty() => {
+            out.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                format_line_break(out, depth + 1, options);
+                format_span(out, "json-key", &quote(key, options));
+                out.push_str(": ");
+                format_value(out, value, depth + 1, options);
+            }
+            format_line_break(out, depth, options);
+            out.push('}');
+        }
+        JsonValue::Array(_) => out.push_str("[]"),
+        JsonValue::Object(_) => out.push_str("{}"),
+    }
+}
+
+fn quote(string: &str, options: &FormatOptions) -> String {
+    let mut quoted = String::new();
+    formatter::format_string(&mut quoted, string, options).unwrap();
+    quoted
+}
+
+fn format_span(out: &mut String, class: &str, text: &str) {
+    out.push_str("<span class=\"");
+    out.push_str(class);
+    out.push_str("\">");
+    escape_html(out, text);
+    out.push_str("</span>");
+}
+
+fn format_line_break(out: &mut String, depth: usize, options: &FormatOptions) {
+    out.push('\n');
+    for _ in 0..depth {
+        out.push_str(&options.indent);
+    }
+}
+
+fn escape_html(out: &mut String, text: &str) {
+    for char in text.chars() {
+        match char {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_html_spans() {
+        let value = crate::parse(r#"{"a": [1.5, true, null], "b": {}}"#).unwrap();
+        assert_eq!(
+            format_html(&value),
+            r#"{
+  <span class="json-key">&quot;a&quot;</span>: [
+    <span class="json-number">1.5</span>,
+    <span class="json-bool">true</span>,
+    <span class="json-null">null</span>
+  ],
+  <span class="json-key">&quot;b&quot;</span>: {}
+}"#
+        );
+    }
+
+    #[test]
+    fn format_html_escapes_strings() {
+        let value = JsonValue::String("<b> & \"x\"".to_string());
+        assert_eq!(
+            format_html(&value),
+            r#"<span class="json-string">&quot;&lt;b&gt; &amp; \&quot;x\&quot;&quot;</span>"#
+        );
+    }
+}
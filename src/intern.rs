@@ -0,0 +1,109 @@
+use crate::parser::{JsonNumber, JsonValue};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// A copy of a `JsonValue` in which equal strings share one allocation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InternedValue {
+    Null,
+    Bool(bool),
+    Number(JsonNumber),
+    String(Rc<str>),
+    Array(Vec<InternedValue>),
+    Object(Vec<(Rc<str>, InternedValue)>),
+}
+
+/// Deep-clones `value`, storing each distinct key and string value only once.
+///
+/// Documents such as API responses repeat the same keys in every element of an
+/// array; after interning, all of those keys point to the same `Rc<str>`.
+pub fn intern(value: &JsonValue) -> InternedValue {
+    intern_value(value, &mut HashSet::new())
+}
+
+fn intern_value(value: &JsonValue, strings: &mut HashSet<Rc<str>>) -> InternedValue {
+    match value {
+        JsonValue::Null => InternedValue::Null,
+        JsonValue::Bool(b) => InternedValue::Bool(*b),
+        JsonValue::Number(n) => InternedValue::Number(*n),
+        JsonValue::String(s) => InternedValue::String(intern_str(s, strings)),
+        JsonValue::Array(values) => InternedValue::Array(
+            values
+                .iter()
+                .map(|value| intern_value(value, strings))
+                .collect(),
+        ),
+        JsonValue::Object(entries) => InternedValue::Object(
+            entries
+                .iter()
+                .map(|(key, value)| (intern_str(key, strings), intern_value(value, strings)))
+                .collect(),
+        ),
+    }
+}
+
+fn intern_str(string: &str, strings: &mut HashSet<Rc<str>>) -> Rc<str> {
+    if let Some(interned) = strings.get(string) {
+        return Rc::clone(interned);
+    }
+    let interned: Rc<str> = Rc::from(string);
+    strings.insert(Rc::clone(&interned));
+    interned
+}
+
+impl InternedValue {
+    /// Converts back to a `JsonValue`, copying every string.
+    pub fn to_value(&self) -> JsonValue {
+        match self {
+            InternedValue::Null => JsonValue::Null,
+            InternedValue::Bool(b) => JsonValue::Bool(*b),
+            InternedValue::Number(n) => JsonValue::Number(*n),
+            InternedValue::String(s) => JsonValue::String(s.to_string()),
+            InternedValue::Array(values) => {
+                JsonValue::Array(values.iter().map(InternedValue::to_value).collect())
+            }
+            InternedValue::Object(entries) => JsonValue::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_shares_repeated_strings() {
+        let records: Vec<String> = (0..1000)
+            .map(|id| format!(r#"{{"id": {}, "status": "active", "owner": "admin"}}"#, id))
+            .collect();
+        let value = crate::parse(&format!("[{}]", records.join(","))).unwrap();
+
+        let interned = intern(&value);
+        assert_eq!(interned.to_value(), value);
+
+        let InternedValue::Array(records) = &interned else {
+            panic!("expected an array");
+        };
+        let InternedValue::Object(first) = &records[0] else {
+            panic!("expected an object");
+        };
+        // Each of the 5 distinct strings is allocated once and shared by all
+        // 1000 records.
+        for (key, _) in first {
+            assert_eq!(Rc::strong_count(key), 1000);
+        }
+        let InternedValue::String(status) = &first[1].1 else {
+            panic!("expected a string");
+        };
+        assert_eq!(Rc::strong_count(status), 1000);
+        let InternedValue::Object(last) = &records[999] else {
+            panic!("expected an object");
+        };
+        assert!(Rc::ptr_eq(&first[2].0, &last[2].0));
+    }
+}
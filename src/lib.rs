@@ -1,3 +1,683 @@
+pub mod access;
+pub mod canonical;
+pub mod comments;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub mod convert;
+pub mod diff;
+pub mod edit;
+pub mod error;
+pub mod events;
 pub mod formatter;
+pub mod html;
+pub mod intern;
+pub mod lint;
+#[cfg(feature = "unicode-normalization")]
+pub mod normalize;
 pub mod parser;
+pub mod query;
+pub mod schema;
+pub mod spanned;
 pub mod tokenizer;
+pub mod transform;
+
+use std::collections::HashSet;
+use std::io::{self, Read};
+use std::slice;
+
+use error::{Error, Position};
+use formatter::FormatOptions;
+use parser::JsonValue;
+use tokenizer::{JsonToken, TokenizeOptions};
+
+/// The default accepts standard JSON, surrounding whitespace and duplicate keys.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    /// Reject any whitespace before or after the top-level value.
+    pub strict_framing: bool,
+    /// Fail with `JsonParserError::DuplicateKey` if an object repeats a key.
+    pub reject_duplicate_keys: bool,
+    /// Fail with `JsonParserError::EmptyKey` if an object has the key `""`.
+    pub reject_empty_keys: bool,
+    /// Extensions to the JSON grammar accepted by the tokenizer.
+    pub tokenize: TokenizeOptions,
+    /// Fail with `JsonTokenizeError::InputTooLarge` before tokenizing if the input
+    /// is longer than this many bytes.
+    pub max_input_bytes: Option<usize>,
+}
+
+impl ParseOptions {
+    /// Enables every check: no surrounding whitespace, no duplicate or empty keys
+    /// and no extensions to the RFC 8259 grammar.
+    pub fn strict() -> Self {
+        ParseOptions {
+            strict_framing: true,
+            reject_duplicate_keys: true,
+            reject_empty_keys: true,
+            tokenize: TokenizeOptions::default(),
+            max_input_bytes: None,
+        }
+    }
+
+    /// Relaxes every check: surrounding whitespace, duplicate and empty keys,
+    /// comments, hex numbers, numeric separators, numbers such as `01` and `1.`,
+    /// raw control characters in strings, literals in any case, Python's `None`
+    /// and single-quoted strings are all accepted, and escaped lone surrogates
+    /// decode as U+FFFD.
+    pub fn lenient() -> Self {
+        ParseOptions {
+            strict_framing: false,
+            reject_duplicate_keys: false,
+            reject_empty_keys: false,
+            tokenize: TokenizeOptions {
+                allow_numeric_separators: true,
+                allow_hex_numbers: true,
+                allow_lax_numbers: true,
+                allow_control_characters: true,
+                allow_comments: true,
+                case_insensitive_literals: true,
+                replacement_char: Some('\u{FFFD}'),
+                max_tokens: None,
+                python_literals: true,
+                allow_single_quotes: true,
+            },
+            max_input_bytes: None,
+        }
+    }
+}
+
+pub fn parse(content: &str) -> Result<JsonValue, Error> {
+    parse_with_options(content, &ParseOptions::default())
+}
+
+pub fn parse_with_options(content: &str, options: &ParseOptions) -> Result<JsonValue, Error> {
+    if let Some(limit) = options.max_input_bytes {
+        if content.len() > limit {
+            return Err(tokenizer::JsonTokenizeError::InputTooLarge(limit).into());
+        }
+    }
+
+    if options.strict_framing {
+        // The byte order mark the tokenizer skips doesn't count as framing.
+        let body = content.strip_prefix('\u{FEFF}').unwrap_or(content);
+        let framing_whitespace = body
+            .char_indices()
+            .next()
+            .filter(|(_, char)| char.is_ascii_whitespace())
+            .or_else(|| {
+                body.char_indices()
+                    .last()
+                    .filter(|(_, char)| char.is_ascii_whitespace())
+            });
+        if let Some((offset, char)) = framing_whitespace {
+            return Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::UnexpectedCharacter(char),
+                Some(Position::at(content, offset + content.len() - body.len())),
+            ));
+        }
+    }
+
+    let (spanned, _) = tokenizer::tokenize_located(content, &options.tokenize)?;
+    let tokens: Vec<_> = spanned
+        .iter()
+        .map(|spanned| spanned.token.clone())
+        .collect();
+    let value = parser::parse_tokens(&tokens)
+        .map_err(|(error, position)| spanned::locate(content, &spanned, position, error))?;
+    // A rejected value may be nested too deeply to be dropped recursively.
+    if options.reject_duplicate_keys {
+        if let Some(key) = find_duplicate_key(&value).map(str::to_string) {
+            value.drop_deep();
+            return Err(parser::JsonParserError::DuplicateKey(key).into());
+        }
+    }
+    if options.reject_empty_keys && has_empty_key(&value) {
+        value.drop_deep();
+        return Err(parser::JsonParserError::EmptyKey.into());
+    }
+    Ok(value)
+}
+
+fn has_empty_key(value: &JsonValue) -> bool {
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            JsonValue::Object(entries) => {
+                for (key, value) in entries {
+                    if key.is_empty() {
+                        return true;
+                    }
+                    stack.push(value);
+                }
+            }
+            JsonValue::Array(values) => stack.extend(values),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Returns the first key repeated within a single object, searching depth-first.
+fn find_duplicate_key(value: &JsonValue) -> Option<&str> {
+    enum Open<'a> {
+        Array(slice::Iter<'a, JsonValue>),
+        /// The remaining entries, and the keys seen so far.
+        Object(slice::Iter<'a, (String, JsonValue)>, HashSet<&'a str>),
+    }
+
+    let mut stack = Vec::new();
+    let mut next = Some(value);
+    loop {
+        match next {
+            Some(JsonValue::Array(values)) => stack.push(Open::Array(values.iter())),
+            Some(JsonValue::Object(entries)) => {
+                stack.push(Open::Object(entries.iter(), HashSet::new()))
+            }
+            _ => {}
+        }
+        next = match stack.last_mut()? {
+            Open::Array(values) => values.next(),
+            Open::Object(entries, keys) => match entries.next() {
+                Some((key, _)) if !keys.insert(key) => return Some(key),
+                Some((_, value)) => Some(value),
+                None => None,
+            },
+        };
+        if next.is_none() {
+            stack.pop();
+        }
+    }
+}
+
+/// Parses a JSON document read from `reader`.
+///
+/// The input is currently read into memory in full before parsing. Input that is
+/// not valid UTF-8 is reported as an `Error::Io` with kind `InvalidData`.
+pub fn parse_reader<R: io::Read>(reader: R) -> Result<JsonValue, Error> {
+    parse_reader_with_options(reader, &ParseOptions::default())
+}
+
+/// Parses a JSON document read from `reader` like `parse_reader`, with `options`.
+///
+/// With `max_input_bytes` set, at most one byte past the limit is read, so an
+/// oversized or endless input fails with `InputTooLarge` without being read in full.
+pub fn parse_reader_with_options<R: io::Read>(
+    mut reader: R,
+    options: &ParseOptions,
+) -> Result<JsonValue, Error> {
+    let mut bytes = Vec::new();
+    match options.max_input_bytes {
+        Some(limit) => {
+            let read = reader
+                .take((limit as u64).saturating_add(1))
+                .read_to_end(&mut bytes)?;
+            if read > limit {
+                return Err(tokenizer::JsonTokenizeError::InputTooLarge(limit).into());
+            }
+        }
+        None => {
+            reader.read_to_end(&mut bytes)?;
+        }
+    }
+    let content = String::from_utf8(bytes)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    parse_with_options(&content, options)
+}
+
+/// Parses the first JSON value in `content` and returns it together with the
+/// byte offset just past its end, so several documents can be read from one buffer.
+///
+/// Anything after the first value is left untouched, even if it isn't valid JSON.
+pub fn parse_prefix(content: &str) -> Result<(JsonValue, usize), Error> {
+    let options = TokenizeOptions::default();
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+
+    let mut stream = tokenizer::spanned_tokens(content, &options);
+    while let Some(spanned) = stream.next() {
+        let spanned = spanned.map_err(|error| {
+            Error::Tokenize(error, Some(Position::at(content, stream.error_offset())))
+        })?;
+        match spanned.token {
+            JsonToken::LeftSquareBracket | JsonToken::LeftCurlyBracket => depth += 1,
+            JsonToken::RightSquareBracket | JsonToken::RightCurlyBracket => {
+                depth = depth.saturating_sub(1)
+            }
+            _ => {}
+        }
+        tokens.push(spanned);
+        if depth == 0 {
+            break;
+        }
+    }
+
+    let mut position = 0;
+    let value = spanned::parse_value(&tokens, &mut position)
+        .map_err(|error| spanned::locate(content, &tokens, position, error))?;
+    Ok((value.to_value(), value.span.end))
+}
+
+/// Returns `string` as a quoted JSON string literal, escaped as the formatter does.
+pub fn escape_string(string: &str) -> String {
+    let mut literal = String::new();
+    formatter::format_string(&mut literal, string, &FormatOptions::default()).unwrap();
+    literal
+}
+
+pub use tokenizer::unescape_string;
+
+pub fn format_str(content: &str, options: &FormatOptions) -> Result<String, Error> {
+    let value = parse(content)?;
+    Ok(formatter::format_with_options(&value, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonNumber;
+
+    #[test]
+    fn parse_strict_framing() {
+        let options = ParseOptions {
+            strict_framing: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(" {}", &options),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::UnexpectedCharacter(' '),
+                Some(Position::at(" {}", 0))
+            ))
+        );
+        assert_eq!(
+            parse_with_options("{}\n", &options),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::UnexpectedCharacter('\n'),
+                Some(Position::at("{}\n", 2))
+            ))
+        );
+        assert_eq!(
+            parse_with_options("{ }", &options),
+            Ok(JsonValue::Object(vec![]))
+        );
+        assert_eq!(
+            parse_with_options("\u{FEFF}{}", &options),
+            Ok(JsonValue::Object(vec![]))
+        );
+        assert_eq!(
+            parse_with_options("\u{FEFF} {}", &options),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::UnexpectedCharacter(' '),
+                Some(Position::at("\u{FEFF} {}", 3))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_leading_byte_order_mark() {
+        assert_eq!(parse("\u{FEFF}[1]"), parse("[1]"));
+        assert_eq!(
+            parse("[1,\u{FEFF}2]"),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::UnexpectedCharacter('\u{FEFF}'),
+                Some(Position::at("[1,\u{FEFF}2]", 3))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_lenient_framing() {
+        assert_eq!(parse(" {}"), Ok(JsonValue::Object(vec![])));
+        assert_eq!(parse("{} "), Ok(JsonValue::Object(vec![])));
+    }
+
+    #[test]
+    fn parse_strict_and_lenient_presets() {
+        let relaxations = [
+            " 1 ",
+            r#"{"a": 1, "a": 2}"#,
+            "1_000",
+            "0x1F",
+            "// comment\n[1, /* two */ 2]",
+            "[TRUE, Null]",
+            "{'a': None, 'b': True}",
+            r#"{"": 1}"#,
+            "01",
+            "1.",
+            "[1.e5]",
+            "\"line\nbreak\"",
+            "\"nul \u{0} tab \t\"",
+        ];
+        for content in relaxations {
+            assert!(
+                parse_with_options(content, &ParseOptions::lenient()).is_ok(),
+                "lenient rejected {:?}",
+                content
+            );
+            assert!(
+                parse_with_options(content, &ParseOptions::strict()).is_err(),
+                "strict accepted {:?}",
+                content
+            );
+        }
+        assert_eq!(
+            parse_with_options(r#"{"a": [1]}"#, &ParseOptions::strict()),
+            parse(r#"{"a": [1]}"#)
+        );
+    }
+
+    #[test]
+    fn parse_lenient_python_repr() {
+        assert_eq!(
+            parse_with_options(
+                "{'a': None, 'b': True, 'c': [False, 'x\"y']}",
+                &ParseOptions::lenient()
+            ),
+            parse(r#"{"a": null, "b": true, "c": [false, "x\"y"]}"#)
+        );
+    }
+
+    #[test]
+    fn parse_scalar_between_comments() {
+        let options = ParseOptions {
+            tokenize: TokenizeOptions {
+                allow_comments: true,
+                ..TokenizeOptions::default()
+            },
+            ..ParseOptions::default()
+        };
+        let number = Ok(JsonValue::Number(JsonNumber::Integer(42)));
+        assert_eq!(parse_with_options("/* c */ 42 // end", &options), number);
+        assert_eq!(parse_with_options("// comment\n 42", &options), number);
+        assert_eq!(parse_with_options("42/**/", &options), number);
+        assert_eq!(
+            parse_with_options("/* c */ 42 /* d */ 43", &options),
+            Err(Error::Parse(
+                parser::JsonParserError::UnexpectedToken(JsonToken::Number(JsonNumber::Integer(
+                    43
+                ))),
+                Some(Position::at("/* c */ 42 /* d */ 43", 19))
+            ))
+        );
+        assert!(parse_with_options("/* c */ 42", &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn parse_max_input_bytes() {
+        let options = ParseOptions {
+            max_input_bytes: Some(8),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options("[1, 2] ", &options),
+            Ok(JsonValue::Array(vec![
+                JsonValue::Number(JsonNumber::Integer(1)),
+                JsonValue::Number(JsonNumber::Integer(2)),
+            ]))
+        );
+        assert!(parse_with_options(r#""héllo""#, &options).is_ok());
+        assert_eq!(
+            parse_with_options("[1, 2, 3]", &options),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::InputTooLarge(8),
+                None
+            ))
+        );
+        // The limit counts bytes, not characters.
+        assert_eq!(
+            parse_with_options(r#""hélloo""#, &options),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::InputTooLarge(8),
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_reject_duplicate_keys() {
+        let content = r#"{"a": 1, "b": [{"c": 1, "c": 2}], "a": 3}"#;
+        assert!(parse(content).is_ok());
+
+        let options = ParseOptions {
+            reject_duplicate_keys: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(content, &options),
+            Err(Error::Parse(
+                parser::JsonParserError::DuplicateKey("c".to_string()),
+                None
+            ))
+        );
+        assert!(parse_with_options(r#"{"a": {"a": 1}}"#, &options).is_ok());
+    }
+
+    #[test]
+    fn parse_empty_key() {
+        let content = r#"{"": {"a": [{"": null}]}}"#;
+        let value = parse(content).unwrap();
+        assert_eq!(
+            formatter::format_with_options(&value, &FormatOptions::single_line()),
+            content
+        );
+
+        let options = ParseOptions {
+            reject_empty_keys: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(r#"{"a": [{"": null}]}"#, &options),
+            Err(Error::Parse(parser::JsonParserError::EmptyKey, None))
+        );
+        assert!(parse_with_options(r#"{"a": [""]}"#, &options).is_ok());
+    }
+
+    #[test]
+    fn parse_deep_nesting_with_key_checks() {
+        let depth = 100_000;
+        let content = format!("{}[]{}", r#"{"a": "#.repeat(depth), "}".repeat(depth));
+        let options = ParseOptions {
+            reject_duplicate_keys: true,
+            reject_empty_keys: true,
+            ..ParseOptions::default()
+        };
+        parse_with_options(&content, &options).unwrap().drop_deep();
+
+        let content = format!(
+            "{}[]{}",
+            r#"{"a": 1, "a": "#.repeat(depth),
+            "}".repeat(depth)
+        );
+        assert_eq!(
+            parse_with_options(&content, &options),
+            Err(Error::Parse(
+                parser::JsonParserError::DuplicateKey("a".to_string()),
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn escape_string_round_trip() {
+        let strings = [
+            "plain",
+            "say \"hi\"",
+            "C:\\path\\",
+            "\u{0000}\u{0008}\n\t\u{001F}",
+            "héllo, 世界 😀 \u{2028}",
+        ];
+        for string in strings {
+            let literal = escape_string(string);
+            assert_eq!(unescape_string(&literal).as_deref(), Ok(string));
+        }
+        assert_eq!(escape_string("a\"b\\c\n"), r#""a\"b\\c\n""#);
+        assert_eq!(unescape_string(r#""\u0048i\/""#), Ok("Hi/".to_string()));
+    }
+
+    #[test]
+    fn numbers_keep_integer_and_float_notation() {
+        let options = FormatOptions::default();
+        assert_eq!(format_str("1", &options), Ok("1".to_string()));
+        assert_eq!(format_str("-20", &options), Ok("-20".to_string()));
+        assert_eq!(format_str("1.0", &options), Ok("1.0".to_string()));
+        assert_eq!(format_str("2.50", &options), Ok("2.5".to_string()));
+        // An exponent makes a float, even when the value is integral.
+        assert_eq!(format_str("1e2", &options), Ok("100.0".to_string()));
+        assert_eq!(
+            parse("9223372036854775807"),
+            Ok(JsonValue::Number(JsonNumber::Integer(i64::MAX)))
+        );
+        assert_eq!(
+            parse("9223372036854775808"),
+            Ok(JsonValue::Number(JsonNumber::Float(9223372036854775808.0)))
+        );
+    }
+
+    #[test]
+    fn numbers_round_trip_negative_zero_and_reject_overflow() {
+        let options = FormatOptions::default();
+        let formatted = format_str("-0", &options).unwrap();
+        assert_eq!(formatted, "-0.0");
+        let Ok(JsonValue::Number(JsonNumber::Float(zero))) = parse(&formatted) else {
+            panic!("expected a float");
+        };
+        assert!(zero == 0.0 && zero.is_sign_negative());
+        assert_eq!(
+            format_str("[-0, 0]", &FormatOptions::single_line()),
+            Ok("[-0.0, 0]".to_string())
+        );
+
+        for content in ["1e999", "-1e999", "[1, 1e400]"] {
+            assert!(
+                matches!(
+                    parse(content),
+                    Err(Error::Tokenize(
+                        tokenizer::JsonTokenizeError::InvalidNumberLiteral(_),
+                        _
+                    ))
+                ),
+                "accepted {:?}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn parse_reader_from_bytes() {
+        let reader: &[u8] = br#"{"a": [1, null]}"#;
+        assert_eq!(parse_reader(reader), parse(r#"{"a": [1, null]}"#));
+
+        let reader: &[u8] = b"[1,";
+        assert_eq!(
+            parse_reader(reader),
+            Err(Error::Parse(
+                parser::JsonParserError::UnexpectedEndOfInput,
+                Some(Position::at("[1,", 3))
+            ))
+        );
+
+        let reader: &[u8] = b"\"\xff\"";
+        assert!(matches!(
+            parse_reader(reader),
+            Err(Error::Io(error)) if error.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[test]
+    fn parse_reader_max_input_bytes() {
+        let options = ParseOptions {
+            max_input_bytes: Some(8),
+            ..ParseOptions::default()
+        };
+        let reader: &[u8] = b"[1, 2]  ";
+        assert_eq!(
+            parse_reader_with_options(reader, &options),
+            parse("[1, 2]  ")
+        );
+        let reader: &[u8] = b"[1, 2, 3]";
+        assert_eq!(
+            parse_reader_with_options(reader, &options),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::InputTooLarge(8),
+                None
+            ))
+        );
+
+        // Only one byte past the limit is read, so an endless input still fails.
+        let endless = b"[".chain(io::repeat(b' '));
+        assert_eq!(
+            parse_reader_with_options(endless, &options),
+            Err(Error::Tokenize(
+                tokenizer::JsonTokenizeError::InputTooLarge(8),
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_prefix_stops_after_first_value() {
+        let content = r#"{"a":1} rest"#;
+        let (value, offset) = parse_prefix(content).unwrap();
+        assert_eq!(
+            value,
+            JsonValue::Object(vec![(
+                "a".to_string(),
+                JsonValue::Number(JsonNumber::Integer(1))
+            )])
+        );
+        assert_eq!(offset, 7);
+        assert_eq!(&content[offset..], " rest");
+    }
+
+    #[test]
+    fn parse_prefix_multiple_documents() {
+        let content = "  42 [true]";
+        let (first, offset) = parse_prefix(content).unwrap();
+        assert_eq!(first, JsonValue::Number(JsonNumber::Integer(42)));
+        assert_eq!(offset, 4);
+
+        let (second, rest) = parse_prefix(&content[offset..]).unwrap();
+        assert_eq!(second, JsonValue::Array(vec![JsonValue::Bool(true)]));
+        assert_eq!(offset + rest, content.len());
+    }
+
+    #[test]
+    fn parse_prefix_incomplete_value() {
+        assert_eq!(
+            parse_prefix(r#"{"a":1"#),
+            Err(Error::Parse(
+                parser::JsonParserError::UnexpectedEndOfInput,
+                Some(Position::at(r#"{"a":1"#, 6))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let missing_comma = "{\n  \"a\": 1\n  \"b\": 2\n}";
+        assert_eq!(parse(missing_comma).unwrap_err().position(), Some((3, 3)));
+
+        // A malformed literal is reported where it starts, at the `@`.
+        let bad_character = "[\n  \"é\", @\n]";
+        assert_eq!(parse(bad_character).unwrap_err().position(), Some((2, 8)));
+        assert_eq!(
+            spanned::parse_spanned(bad_character)
+                .unwrap_err()
+                .position(),
+            Some((2, 8))
+        );
+        let unterminated = "[\n  \"a\", \"b\\x\"\n]";
+        assert_eq!(parse(unterminated).unwrap_err().position(), Some((2, 8)));
+
+        assert_eq!(parse("[1,\n").unwrap_err().position(), Some((2, 1)));
+        let options = ParseOptions {
+            reject_empty_keys: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(r#"{"": 1}"#, &options)
+                .unwrap_err()
+                .position(),
+            None
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use crate::diff::{diff, DiffOp};
+use crate::error::Error;
+use crate::formatter::FormatOptions;
+use crate::parser::JsonValue;
+use crate::query::{leaves, push_segment, LeafKind};
+use std::collections::HashMap;
+
+/// The largest integer a JavaScript number holds exactly, 2^53 - 1.
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+#[derive(Debug, PartialEq)]
+pub struct LintFinding {
+    /// 1-based line number in the linted content.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Compares `content` with its formatted form line by line and reports what differs.
+///
+/// Lines are matched on their trimmed content, so a line that is only misindented
+/// is reported as an indentation problem rather than as a missing and an extra line.
+pub fn lint(content: &str, options: &FormatOptions) -> Result<Vec<LintFinding>, Error> {
+    let formatted = crate::format_str(content, options)?;
+
+    let actual_lines: Vec<&str> = content.lines().collect();
+    let expected_lines: Vec<&str> = formatted.lines().collect();
+    let actual_trimmed: Vec<&str> = actual_lines.iter().map(|line| line.trim()).collect();
+    let expected_trimmed: Vec<&str> = expected_lines.iter().map(|line| line.trim()).collect();
+
+    let mut findings = Vec::new();
+    let mut line = 1;
+
+    for op in diff(&actual_trimmed, &expected_trimmed) {
+        match op {
+            DiffOp::Equal(actual_index, expected_index) => {
+                let actual = actual_lines[actual_index];
+                let expected = expected_lines[expected_index];
+                let found_indent = leading_whitespace(actual);
+                let expected_indent = leading_whitespace(expected);
+                if found_indent != expected_indent {
+                    findings.push(LintFinding {
+                        line,
+                        message: format!(
+                            "expected {}, found {}",
+                            describe_indent(expected_indent),
+                            describe_indent(found_indent)
+                        ),
+                    });
+                }
+                if actual.len() != actual.trim_end().len() {
+                    findings.push(LintFinding {
+                        line,
+                        message: "trailing whitespace".to_string(),
+                    });
+                }
+                line += 1;
+            }
+            DiffOp::Delete(actual_index) => {
+                findings.push(LintFinding {
+                    line,
+                    message: format!("unexpected `{}`", actual_trimmed[actual_index]),
+                });
+                line += 1;
+            }
+            DiffOp::Insert(expected_index) => {
+                findings.push(LintFinding {
+                    line,
+                    message: format!("expected `{}`", expected_trimmed[expected_index]),
+                });
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Returns the 1-based numbers of the lines of `formatted` longer than `max`
+/// characters.
+pub fn lint_line_width(formatted: &str, max: usize) -> Vec<usize> {
+    formatted
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.chars().count() > max)
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+/// Returns the 1-based numbers of the lines of `content` indented with both tabs
+/// and spaces. The first indented line sets the expected character, so a line
+/// indented only with the other one is reported too.
+pub fn detect_mixed_indent(content: &str) -> Vec<usize> {
+    let mut expected = None;
+    let mut lines = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let indent = leading_whitespace(line);
+        let Some(first) = indent.chars().next() else {
+            continue;
+        };
+        let expected = *expected.get_or_insert(first);
+        if indent.chars().any(|char| char != expected) {
+            lines.push(index + 1);
+        }
+    }
+    lines
+}
+
+/// Returns the JSON Pointer and value of every integer in `value` outside the
+/// range JavaScript numbers represent exactly, ±(2^53 - 1), in document order.
+///
+/// Floats with an integral value count too, since integers too large for an
+/// `i64` are parsed as floats.
+pub fn find_unsafe_integers(value: &JsonValue) -> Vec<(String, f64)> {
+    leaves(value)
+        .into_iter()
+        .filter_map(|(path, kind, value)| match (kind, value) {
+            (LeafKind::Number, JsonValue::Number(number)) => Some((path, number.as_f64())),
+            _ => None,
+        })
+        .filter(|(_, number)| number.fract() == 0.0 && number.abs() > MAX_SAFE_INTEGER)
+        .collect()
+}
+
+/// Returns the JSON Pointer of each object with keys that differ only in case,
+/// such as `Name` and `name`, with the colliding keys in document order. An
+/// object with several groups of colliding keys is listed once per group. A key
+/// repeated exactly is not a collision.
+pub fn find_case_collisions(value: &JsonValue) -> Vec<(String, Vec<String>)> {
+    let mut collisions = Vec::new();
+    collect_case_collisions(value, &mut String::new(), &mut collisions);
+    collisions
+}
+
+fn collect_case_collisions(
+    value: &JsonValue,
+    path: &mut String,
+    collisions: &mut Vec<(String, Vec<String>)>,
+) {
+    match value {
+        JsonValue::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                collect_case_collisions(value, path, collisions);
+                path.truncate(len);
+            }
+        }
+        JsonValue::Object(entries) => {
+            let mut groups: Vec<Vec<String>> = Vec::new();
+            let mut group_of_key = HashMap::new();
+            for (key, _) in entries {
+                let group = *group_of_key.entry(key.to_lowercase()).or_insert_with(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+                if !groups[group].contains(key) {
+                    groups[group].push(key.clone());
+                }
+            }
+            collisions.extend(
+                groups
+                    .into_iter()
+                    .filter(|keys| keys.len() > 1)
+                    .map(|keys| (path.clone(), keys)),
+            );
+
+            for (key, value) in entries {
+                let len = path.len();
+                push_segment(path, key);
+                collect_case_collisions(value, path, collisions);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn describe_indent(indent: &str) -> String {
+    if indent.is_empty() {
+        "no indent".to_string()
+    } else if indent.chars().all(|char| char == ' ') {
+        format!("{}-space indent", indent.len())
+    } else if indent.chars().all(|char| char == '\t') {
+        format!("{}-tab indent", indent.len())
+    } else {
+        format!("{:?} indent", indent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_misindented_file() {
+        let content = "{\n    \"a\": 1,\n  \"b\": [ \n  true\n  ]\n}\n";
+        let findings = lint(content, &FormatOptions::default()).unwrap();
+        let messages: Vec<String> = findings.iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 2: expected 2-space indent, found 4-space indent",
+                "line 3: trailing whitespace",
+                "line 4: expected 4-space indent, found 2-space indent",
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_clean_file() {
+        let content = "{\n  \"a\": 1,\n  \"b\": [\n    true\n  ]\n}\n";
+        let findings = lint(content, &FormatOptions::default()).unwrap();
+        assert_eq!(findings, vec![]);
+    }
+
+    #[test]
+    fn lint_reports_line_structure_changes() {
+        let content = "[1, 2]";
+        let findings = lint(content, &FormatOptions::default()).unwrap();
+        let messages: Vec<String> = findings.iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 1: unexpected `[1, 2]`",
+                "line 2: expected `[`",
+                "line 2: expected `1,`",
+                "line 2: expected `2`",
+                "line 2: expected `]`",
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_line_width_reports_long_lines() {
+        let value = crate::parse(
+            r#"{"short": 1, "long": "a string that runs past the limit", "list": ["é", "ü"]}"#,
+        )
+        .unwrap();
+        let formatted = crate::formatter::format(&value);
+        assert_eq!(lint_line_width(&formatted, 20), vec![3]);
+        assert_eq!(lint_line_width(&formatted, 8), vec![2, 3, 4]);
+        assert_eq!(lint_line_width(&formatted, 60), Vec::<usize>::new());
+        // Width counts characters rather than bytes: `    "é",` is 8 wide.
+        assert_eq!(lint_line_width(&formatted, 7), vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn detect_mixed_indent_consistent() {
+        assert_eq!(
+            detect_mixed_indent("{\n  \"a\": [\n    1\n  ]\n}\n"),
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            detect_mixed_indent("{\n\t\"a\": [\n\t\t1\n\t]\n}\n"),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn detect_mixed_indent_reports_lines() {
+        let content = "{\n  \"a\": [\n\t\t1,\n  \t2\n  ]\n}\n";
+        assert_eq!(detect_mixed_indent(content), vec![3, 4]);
+        assert_eq!(detect_mixed_indent("[\n\t1,\n\t 2\n]"), vec![3]);
+    }
+
+    #[test]
+    fn find_unsafe_integers_reports_paths() {
+        let value = crate::parse(
+            r#"{"id": 9007199254740993, "ok": [9007199254740991, -9007199254740991, 1.5],
+                "big": [-9007199254740992, 1e300, 123456789012345678901]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            find_unsafe_integers(&value),
+            vec![
+                ("/id".to_string(), 9007199254740993.0),
+                ("/big/0".to_string(), -9007199254740992.0),
+                ("/big/1".to_string(), 1e300),
+                ("/big/2".to_string(), 123456789012345678901.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_unsafe_integers_within_range() {
+        let value =
+            crate::parse(r#"{"a": [0, -1, 2.5e15, 9007199254740991], "b": "9007199254740993"}"#)
+                .unwrap();
+        assert_eq!(find_unsafe_integers(&value), vec![]);
+    }
+
+    #[test]
+    fn find_case_collisions_reports_keys() {
+        let value = crate::parse(
+            r#"{"Name": "a", "name": "b", "id": 1, "users": [{"ID": 1, "Id": 2, "id": 3, "ok": 1, "ok": 2}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            find_case_collisions(&value),
+            vec![
+                ("".to_string(), vec!["Name".to_string(), "name".to_string()]),
+                (
+                    "/users/0".to_string(),
+                    vec!["ID".to_string(), "Id".to_string(), "id".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_case_collisions_without_collisions() {
+        let value =
+            crate::parse(r#"{"name": "a", "nested": {"name": "b"}, "list": [{"Name": 1}]}"#)
+                .unwrap();
+        assert_eq!(find_case_collisions(&value), vec![]);
+    }
+}
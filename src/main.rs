@@ -1,81 +1,478 @@
+use std::io::{self, Read};
 use std::{env, fs};
 
-use json_formatter::formatter;
-use json_formatter::parser;
-use json_formatter::tokenizer;
+use json_formatter::error::Error;
+use json_formatter::parser::{JsonParserError, JsonValue};
+use json_formatter::ParseOptions;
+use json_formatter::{formatter, lint};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    /// Pretty-print the input. This is the default when no command is given.
+    Format,
+    /// Print the input on a single line without whitespace.
+    Minify,
+    /// Exit with an error if the input is not already formatted.
+    Check,
+    /// Exit with an error if the input is not valid JSON.
+    Validate,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Command> {
+        match name {
+            "format" => Some(Command::Format),
+            "minify" => Some(Command::Minify),
+            "check" => Some(Command::Check),
+            "validate" => Some(Command::Validate),
+            _ => None,
+        }
+    }
+
+    /// Whether the command takes layout options such as `--indent`.
+    fn has_layout(self) -> bool {
+        matches!(self, Command::Format | Command::Check)
+    }
+
+    /// Whether the command writes the document, and so takes `-o`, `-w` and `--raw`.
+    fn has_output(self) -> bool {
+        matches!(self, Command::Format | Command::Minify)
+    }
+}
+
+/// The options that only apply to commands with output or layout.
+const OUTPUT_OPTIONS: &[&str] = &[
+    "-o",
+    "--output",
+    "-w",
+    "--write",
+    "--dry-run",
+    "--raw",
+    "--bom",
+    "--combine",
+    "--flatten",
+    "--indent",
+    "--array-indent",
+    "--tab-width",
+    "--inline-array-width",
+];
 
 fn main() {
-    let mut args = env::args();
+    let args: Vec<String> = env::args().skip(1).collect();
+    // `--validate` is looked for before reading the other options, so that it
+    // can be given anywhere and the options it rules out are rejected.
+    let validate_flag = args.iter().any(|arg| arg == "--validate");
+    let mut args = args.into_iter().peekable();
+
+    let command = match args.peek().and_then(|arg| Command::from_name(arg)) {
+        Some(command) => {
+            args.next();
+            command
+        }
+        None if validate_flag => Command::Validate,
+        None => Command::Format,
+    };
 
-    args.next();
+    let mut filenames = Vec::new();
+    let mut output = None;
+    let mut write = false;
+    let mut dry_run = false;
+    let mut raw = false;
+    let mut combine = false;
+    let mut flatten = false;
+    let mut mmap = false;
+    let mut options = formatter::FormatOptions {
+        compact: command == Command::Minify,
+        ..formatter::FormatOptions::default()
+    };
+    let mut parse_options = ParseOptions::default();
 
-    let filename = match args.next() {
-        Some(filename) => filename,
-        None => {
-            eprintln!("No filename provided");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--validate" if command == Command::Validate => {}
+            "-o" | "--output" if command.has_output() => {
+                output = Some(option_value(&mut args, &arg))
+            }
+            "-w" | "--write" if command.has_output() => write = true,
+            "--dry-run" if command.has_output() => dry_run = true,
+            "--raw" if command.has_output() => raw = true,
+            "--bom" if command.has_output() => options.emit_bom = true,
+            "--combine" if command.has_output() => combine = true,
+            "--flatten" if command.has_output() => flatten = true,
+            "--indent" if command.has_layout() => {
+                options.indent = parse_indent(&arg, &option_value(&mut args, &arg))
+            }
+            "--array-indent" if command.has_layout() => {
+                options.array_indent = Some(parse_indent(&arg, &option_value(&mut args, &arg)));
+            }
+            "--tab-width" if command.has_layout() => {
+                options.tab_width = parse_count(&arg, &option_value(&mut args, &arg));
+            }
+            "--inline-array-width" if command.has_layout() => {
+                options.inline_array_width =
+                    Some(parse_count(&arg, &option_value(&mut args, &arg)));
+            }
+            "--no-duplicate-keys" => parse_options.reject_duplicate_keys = true,
+            "--mmap" if cfg!(feature = "mmap") => mmap = true,
+            _ if command == Command::Validate && OUTPUT_OPTIONS.contains(&arg.as_str()) => {
+                let validate = if validate_flag {
+                    "--validate"
+                } else {
+                    "validate"
+                };
+                eprintln!("Option '{}' can't be used with '{}'", arg, validate);
+                std::process::exit(1);
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option: '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => filenames.push(arg),
+        }
+    }
+
+    // Commands that write the document take several files only to combine them
+    // or to write each in place.
+    if command.has_output() && !combine && !write && filenames.len() > 1 {
+        eprintln!("Unexpected argument: '{}'", filenames[1]);
+        std::process::exit(1);
+    }
+    if flatten && !combine {
+        eprintln!("Option '--flatten' requires '--combine'");
+        std::process::exit(1);
+    }
+    if dry_run && !write {
+        eprintln!("Option '--dry-run' requires '-w'");
+        std::process::exit(1);
+    }
+    if write {
+        let conflict = [
+            (output.is_some(), "-o"),
+            (combine, "--combine"),
+            (raw, "--raw"),
+        ]
+        .into_iter()
+        .find_map(|(set, option)| set.then_some(option));
+        if let Some(option) = conflict {
+            eprintln!("Options '-w' and '{}' can't be used together", option);
             std::process::exit(1);
         }
-    };
+        if filenames.is_empty() {
+            eprintln!("Option '-w' requires at least one file");
+            std::process::exit(1);
+        }
+        let failed = write_in_place(&filenames, dry_run, mmap, &options, &parse_options);
+        std::process::exit(if failed { 1 } else { 0 });
+    }
 
-    let content = match fs::read_to_string(&filename) {
-        Ok(content) => content,
-        Err(error) => {
-            match error.kind() {
-                std::io::ErrorKind::NotFound => {
-                    eprintln!("No such file or directory: '{}'", filename);
+    if filenames.len() > 1 && !command.has_output() {
+        let mut failed = false;
+        for filename in &filenames {
+            let content = read_file(filename, mmap);
+            let passed = match parse_document(
+                &content,
+                Some(filename),
+                true,
+                command == Command::Validate,
+                &parse_options,
+            ) {
+                Some(parsed) => {
+                    let passed = command != Command::Check
+                        || is_formatted(&content, &parsed, &options, filename);
+                    parsed.drop_deep();
+                    passed
                 }
-                std::io::ErrorKind::PermissionDenied => {
-                    eprintln!("Permission denied: '{}'", filename);
-                }
-                _ => {
-                    eprintln!("Error reading file '{}': {}", filename, error);
+                None => false,
+            };
+            failed |= !passed;
+        }
+        std::process::exit(if failed { 1 } else { 0 });
+    }
+
+    let parsed = if combine {
+        combine_files(&filenames, flatten, mmap, &parse_options)
+    } else {
+        let filename = filenames.pop();
+        let content = match &filename {
+            Some(filename) => read_file(filename, mmap),
+            None => Content::Read(read_stdin()),
+        };
+
+        let Some(parsed) = parse_document(
+            &content,
+            filename.as_deref(),
+            false,
+            command == Command::Validate,
+            &parse_options,
+        ) else {
+            std::process::exit(1);
+        };
+
+        match command {
+            Command::Validate => {
+                parsed.drop_deep();
+                return;
+            }
+            Command::Check => {
+                let name = filename.as_deref().unwrap_or("<stdin>");
+                if !is_formatted(&content, &parsed, &options, name) {
+                    std::process::exit(1);
                 }
+                parsed.drop_deep();
+                return;
             }
-            std::process::exit(1);
+            Command::Format | Command::Minify => {}
         }
+        parsed
+    };
+
+    let formatted = match &parsed {
+        // Print a top-level string as its plain text, for use in shell scripts.
+        JsonValue::String(string) if raw => string.clone(),
+        _ => formatter::format_with_options(&parsed, &options),
     };
 
-    let mut tokens = match tokenizer::tokenize(&content) {
-        Ok(tokens) => tokens,
+    match output {
+        Some(path) => {
+            if let Err(error) = fs::write(&path, format!("{}\n", formatted)) {
+                eprintln!("Error writing file '{}': {}", path, error);
+                std::process::exit(1);
+            }
+        }
+        None => println!("{}", formatted),
+    }
+    parsed.drop_deep();
+}
+
+/// Parses `content`, printing the error if it isn't valid. Input may be nested
+/// too deeply to drop the value recursively, so callers use `drop_deep`.
+///
+/// The error names the
+/// file when `filename` is given and either `always_name_file` is set, for runs
+/// over several files, or the error is a duplicate key. With `show_position`, it
+/// also gives the line and column the error was found at, when known.
+fn parse_document(
+    content: &str,
+    filename: Option<&str>,
+    always_name_file: bool,
+    show_position: bool,
+    options: &ParseOptions,
+) -> Option<JsonValue> {
+    match json_formatter::parse_with_options(content, options) {
+        Ok(parsed) => Some(parsed),
         Err(error) => {
-            match error {
-                tokenizer::JsonTokenizeError::InvalidEscapeCharacter(character) => {
-                    eprintln!("Invalid escape character: '{}'", character);
-                }
-                tokenizer::JsonTokenizeError::InvalidNumberLiteral(literal) => {
-                    eprintln!("Invalid number literal: '{}'", literal);
-                }
-                tokenizer::JsonTokenizeError::UnexpectedCharacter(character) => {
-                    eprintln!("Unexpected character: '{}'", character);
-                }
-                tokenizer::JsonTokenizeError::UnexpectedEndOfInput => {
-                    eprintln!("Unexpected end of input");
-                }
-                tokenizer::JsonTokenizeError::UnexpectedLiteral(literal) => {
-                    eprintln!("Unexpected literal: '{}'", literal);
+            let duplicate_key = matches!(error, Error::Parse(JsonParserError::DuplicateKey(_), _));
+            let mut message = error.to_string();
+            if let Some((line, column)) = error.position().filter(|_| show_position) {
+                message.push_str(&format!(" at line {}, column {}", line, column));
+            }
+            match filename {
+                Some(filename) if always_name_file || duplicate_key => {
+                    eprintln!("{} in '{}'", message, filename);
                 }
+                _ => eprintln!("{}", message),
             }
+            None
+        }
+    }
+}
+
+/// Parses each file and collects the values into one array. With `flatten`, the
+/// elements of a file containing an array are added instead of the array itself.
+fn combine_files(
+    filenames: &[String],
+    flatten: bool,
+    mmap: bool,
+    options: &ParseOptions,
+) -> JsonValue {
+    let mut values = Vec::new();
+    for filename in filenames {
+        let content = read_file(filename, mmap);
+        match parse_document(&content, Some(filename), true, false, options) {
+            Some(JsonValue::Array(elements)) if flatten => values.extend(elements),
+            Some(value) => values.push(value),
+            None => std::process::exit(1),
+        }
+    }
+    JsonValue::Array(values)
+}
+
+/// Formats each file in place, or with `dry_run` only prints the name of each
+/// file that would change. Returns whether any file could not be formatted.
+fn write_in_place(
+    filenames: &[String],
+    dry_run: bool,
+    mmap: bool,
+    options: &formatter::FormatOptions,
+    parse_options: &ParseOptions,
+) -> bool {
+    let mut failed = false;
+    for filename in filenames {
+        let content = read_file(filename, mmap);
+        let Some(parsed) = parse_document(&content, Some(filename), true, false, parse_options)
+        else {
+            failed = true;
+            continue;
+        };
+        let formatted = format!("{}\n", formatter::format_with_options(&parsed, options));
+        parsed.drop_deep();
+        let changed = *content != formatted;
+        // A mapped file must not be modified while the mapping is alive.
+        drop(content);
+
+        if !changed {
+            continue;
+        }
+        if dry_run {
+            println!("{}", filename);
+        } else if let Err(error) = fs::write(filename, formatted) {
+            eprintln!("Error writing file '{}': {}", filename, error);
+            failed = true;
+        }
+    }
+    failed
+}
+
+/// Returns whether `content` is already formatted, printing a message if it isn't.
+fn is_formatted(
+    content: &str,
+    parsed: &JsonValue,
+    options: &formatter::FormatOptions,
+    name: &str,
+) -> bool {
+    let formatted = formatter::format_with_options(parsed, options);
+    if content.strip_suffix('\n') == Some(formatted.as_str()) {
+        return true;
+    }
+
+    // Options such as `--indent tab --array-indent 2` mix tabs and spaces
+    // themselves, so only lines the formatter would write differently count.
+    let expected: Vec<&str> = formatted.lines().collect();
+    let actual: Vec<&str> = content.lines().collect();
+    let mixed: Vec<String> = lint::detect_mixed_indent(content)
+        .into_iter()
+        .filter(|&line| expected.get(line - 1) != actual.get(line - 1))
+        .map(|line| line.to_string())
+        .collect();
+    if !mixed.is_empty() {
+        let noun = if mixed.len() == 1 { "line" } else { "lines" };
+        eprintln!(
+            "'{}' mixes tabs and spaces in the indentation of {} {}",
+            name,
+            noun,
+            mixed.join(", ")
+        );
+        return false;
+    }
+    eprintln!("'{}' is not formatted", name);
+    false
+}
+
+fn option_value(args: &mut impl Iterator<Item = String>, option: &str) -> String {
+    match args.next() {
+        Some(value) => value,
+        None => {
+            eprintln!("Missing value for option '{}'", option);
             std::process::exit(1);
         }
-    };
+    }
+}
+
+fn parse_count(option: &str, value: &str) -> usize {
+    match value.parse() {
+        Ok(count) => count,
+        Err(_) => {
+            eprintln!("Invalid value for option '{}': '{}'", option, value);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_indent(option: &str, value: &str) -> String {
+    if value == "tab" {
+        "\t".to_string()
+    } else {
+        " ".repeat(parse_count(option, value))
+    }
+}
+
+/// The text of an input, read into a string or, with `--mmap`, mapped from its
+/// file. A mapped file is checked to be UTF-8 in one pass and then tokenized in
+/// place, not decoded or tokenized incrementally, so mapping only saves the copy
+/// into a string: the tokens and the parsed value are still built in full.
+enum Content {
+    Read(String),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Content {
+    /// Maps `filename` into memory, checking once that it is valid UTF-8.
+    #[cfg(feature = "mmap")]
+    fn map(filename: &str) -> io::Result<Content> {
+        let file = fs::File::open(filename)?;
+        // SAFETY: the file must not be modified while mapped, as for any reader
+        // that holds onto its input.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        if std::str::from_utf8(&map).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            ));
+        }
+        Ok(Content::Mapped(map))
+    }
+
+    /// `--mmap` is only accepted with the `mmap` feature.
+    #[cfg(not(feature = "mmap"))]
+    fn map(_filename: &str) -> io::Result<Content> {
+        unreachable!("'--mmap' requires the 'mmap' feature")
+    }
+}
 
-    let parsed = match parser::parser(&mut tokens) {
-        Ok(parsed) => parsed,
+impl std::ops::Deref for Content {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Content::Read(content) => content,
+            // SAFETY: `Content::map` checked that the mapped bytes are UTF-8.
+            #[cfg(feature = "mmap")]
+            Content::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+}
+
+fn read_file(filename: &str, mmap: bool) -> Content {
+    let content = if mmap {
+        Content::map(filename)
+    } else {
+        fs::read_to_string(filename).map(Content::Read)
+    };
+    match content {
+        Ok(content) => content,
         Err(error) => {
-            match error {
-                parser::JsonParserError::UnexpectedEndOfInput => {
-                    eprintln!("Unexpected end of input");
+            match error.kind() {
+                std::io::ErrorKind::NotFound => {
+                    eprintln!("No such file or directory: '{}'", filename);
+                }
+                std::io::ErrorKind::PermissionDenied => {
+                    eprintln!("Permission denied: '{}'", filename);
                 }
-                parser::JsonParserError::UnexpectedToken(token) => {
-                    eprintln!("Unexpected token: '{}'", token);
+                _ => {
+                    eprintln!("Error reading file '{}': {}", filename, error);
                 }
             }
-
             std::process::exit(1);
         }
-    };
-
-    let formatted = formatter::format(&parsed);
+    }
+}
 
-    println!("{}", formatted);
+fn read_stdin() -> String {
+    let mut content = String::new();
+    if let Err(error) = io::stdin().read_to_string(&mut content) {
+        eprintln!("Error reading standard input: {}", error);
+        std::process::exit(1);
+    }
+    content
 }
@@ -0,0 +1,91 @@
+use crate::parser::JsonValue;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalizationForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+/// Normalizes every string value in `value`, at any depth, to the given form.
+///
+/// Keys are left alone; see `normalize_keys`.
+pub fn normalize_strings(value: &mut JsonValue, form: NormalizationForm) {
+    match value {
+        JsonValue::String(string) => *string = normalize(string, form),
+        JsonValue::Array(values) => {
+            for value in values {
+                normalize_strings(value, form);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (_, value) in entries {
+                normalize_strings(value, form);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Normalizes every object key in `value`, at any depth, to the given form.
+///
+/// This can make two keys of an object equal, which then both remain.
+pub fn normalize_keys(value: &mut JsonValue, form: NormalizationForm) {
+    match value {
+        JsonValue::Array(values) => {
+            for value in values {
+                normalize_keys(value, form);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (key, value) in entries {
+                *key = normalize(key, form);
+                normalize_keys(value, form);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn normalize(string: &str, form: NormalizationForm) -> String {
+    match form {
+        NormalizationForm::Nfc => string.nfc().collect(),
+        NormalizationForm::Nfd => string.nfd().collect(),
+        NormalizationForm::Nfkc => string.nfkc().collect(),
+        NormalizationForm::Nfkd => string.nfkd().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSED: &str = "caf\u{00E9}";
+    const DECOMPOSED: &str = "cafe\u{0301}";
+
+    fn document(key: &str, value: &str) -> JsonValue {
+        JsonValue::Object(vec![(
+            key.to_string(),
+            JsonValue::Array(vec![JsonValue::String(value.to_string())]),
+        )])
+    }
+
+    #[test]
+    fn normalize_strings_nfc() {
+        let mut value = document(DECOMPOSED, DECOMPOSED);
+        normalize_strings(&mut value, NormalizationForm::Nfc);
+        assert_eq!(value, document(DECOMPOSED, COMPOSED));
+
+        normalize_strings(&mut value, NormalizationForm::Nfd);
+        assert_eq!(value, document(DECOMPOSED, DECOMPOSED));
+    }
+
+    #[test]
+    fn normalize_keys_nfc() {
+        let mut value = document(DECOMPOSED, DECOMPOSED);
+        normalize_keys(&mut value, NormalizationForm::Nfc);
+        assert_eq!(value, document(COMPOSED, DECOMPOSED));
+    }
+}
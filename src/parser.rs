@@ -1,175 +1,586 @@
 use crate::tokenizer::JsonToken;
-use std::{iter::Peekable, slice::Iter};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
-#[derive(Debug, PartialEq)]
+/// `Hash` is consistent with the derived, positional `PartialEq`: objects with
+/// the same entries in a different order are neither equal nor hashed alike. Use
+/// `semantic_eq` to compare them as maps.
+///
+/// Parsing and formatting a value work at any nesting depth. Dropping it, the
+/// derived `Clone`, `PartialEq` and `Hash`, like most other functions walking a
+/// value, recurse once per level and can overflow the stack on values nested
+/// tens of thousands of levels deep. Use `drop_deep` to drop such a value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum JsonValue {
     Null,
     Bool(bool),
-    Number(f64),
+    Number(JsonNumber),
     String(String),
     Array(Vec<JsonValue>),
     Object(Vec<(String, JsonValue)>),
 }
 
-#[derive(Debug, PartialEq)]
-pub enum JsonParserError {
-    UnexpectedToken(JsonToken),
-    UnexpectedEndOfInput,
+/// A number, which remembers whether it was written as an integer.
+///
+/// A number written without a fraction or exponent, such as `1` or `-20`, is an
+/// `Integer` if it fits in an `i64`. Any other number is a `Float`, including
+/// `1.0` and `1e2`, which therefore format as `1.0` and `100.0`, and `-0`, which
+/// formats as `-0.0` so that its sign is kept.
+#[derive(Debug, Clone, Copy)]
+pub enum JsonNumber {
+    Integer(i64),
+    Float(f64),
 }
 
-pub fn parser(tokens: &Vec<JsonToken>) -> Result<JsonValue, JsonParserError> {
-    let mut tokens = tokens.iter().peekable();
-    let result = parser_value(&mut tokens);
-    if let Some(&token) = tokens.peek() {
-        return Err(JsonParserError::UnexpectedToken(token.clone()));
-    };
-    result
+impl JsonNumber {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            JsonNumber::Integer(n) => n as f64,
+            JsonNumber::Float(n) => n,
+        }
+    }
 }
 
-fn parser_value(
-    mut tokens: &mut Peekable<Iter<'_, JsonToken>>,
-) -> Result<JsonValue, JsonParserError> {
-    if let Some(&token) = tokens.peek() {
-        match token {
-            JsonToken::Null => {
-                tokens.next();
-                Ok(JsonValue::Null)
+/// Floats compare as `f64` does, except that every NaN is equal to every other
+/// NaN, so that equality is reflexive and `Eq` holds even though `Float` and
+/// `From<f64>` accept NaN. Parsing never produces one.
+impl PartialEq for JsonNumber {
+    fn eq(&self, other: &JsonNumber) -> bool {
+        match (*self, *other) {
+            (JsonNumber::Integer(a), JsonNumber::Integer(b)) => a == b,
+            (JsonNumber::Float(a), JsonNumber::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for JsonNumber {}
+
+/// Floats hash by their bits, with `-0.0` hashed as `0.0` and every NaN hashed
+/// alike, since they are equal.
+impl Hash for JsonNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            JsonNumber::Integer(n) => {
+                state.write_u8(0);
+                n.hash(state);
             }
-            JsonToken::True => {
-                tokens.next();
-                Ok(JsonValue::Bool(true))
+            JsonNumber::Float(n) => {
+                state.write_u8(1);
+                let n = if n == 0.0 {
+                    0.0
+                } else if n.is_nan() {
+                    f64::NAN
+                } else {
+                    n
+                };
+                n.to_bits().hash(state);
             }
-            JsonToken::False => {
-                tokens.next();
-                Ok(JsonValue::Bool(false))
+        }
+    }
+}
+
+impl From<i64> for JsonNumber {
+    fn from(n: i64) -> Self {
+        JsonNumber::Integer(n)
+    }
+}
+
+impl From<f64> for JsonNumber {
+    fn from(n: f64) -> Self {
+        JsonNumber::Float(n)
+    }
+}
+
+/// Writes integers as they are and floats with at least one fractional digit.
+impl fmt::Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonNumber::Integer(n) => write!(f, "{}", n),
+            JsonNumber::Float(n) if n.is_finite() && n.fract() == 0.0 => write!(f, "{:.1}", n),
+            JsonNumber::Float(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl JsonValue {
+    /// Drops `self` using an explicit stack rather than recursing once per level,
+    /// for values that may be nested too deeply for the stack.
+    pub fn drop_deep(self) {
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            match value {
+                JsonValue::Array(values) => stack.extend(values),
+                JsonValue::Object(entries) => {
+                    stack.extend(entries.into_iter().map(|(_, value)| value))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Compares values by meaning rather than by structure: objects are compared
+    /// as unordered maps, where a repeated key counts with its last value, and
+    /// numbers are equal when they differ only by floating point rounding.
+    ///
+    /// The derived `PartialEq` stays strict and also compares key order.
+    pub fn semantic_eq(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => {
+                let (a, b) = (a.as_f64(), b.as_f64());
+                a == b || (a - b).abs() <= f64::EPSILON * a.abs().max(b.abs())
             }
-            JsonToken::Number(number) => {
-                tokens.next();
-                Ok(JsonValue::Number(*number))
+            (JsonValue::Array(a), JsonValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.semantic_eq(b))
             }
-            JsonToken::String(string) => {
-                tokens.next();
-                Ok(JsonValue::String(string.clone()))
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                let (a, b) = (last_values(a), last_values(b));
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.semantic_eq(b)))
             }
-            JsonToken::LeftSquareBracket => parser_array(&mut tokens),
-            JsonToken::LeftCurlyBracket => parser_object(&mut tokens),
-            _ => Err(JsonParserError::UnexpectedToken(token.clone())),
+            _ => self == other,
         }
-    } else {
-        Err(JsonParserError::UnexpectedEndOfInput)
+    }
+
+    /// Builds an object from `entries`, failing on the first key that repeats
+    /// an earlier one. Keys of nested objects are not checked.
+    pub fn object_checked(entries: Vec<(String, JsonValue)>) -> Result<JsonValue, DuplicateKey> {
+        let mut keys = HashSet::new();
+        if let Some((key, _)) = entries.iter().find(|(key, _)| !keys.insert(key.as_str())) {
+            return Err(DuplicateKey(key.clone()));
+        }
+        Ok(JsonValue::Object(entries))
+    }
+}
+
+/// The error returned by `JsonValue::object_checked`, with the repeated key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateKey(pub String);
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Duplicate key: '{}'", self.0)
     }
 }
 
-fn parser_object(
-    mut tokens: &mut Peekable<Iter<'_, JsonToken>>,
-) -> Result<JsonValue, JsonParserError> {
-    let mut object = Vec::new();
+impl std::error::Error for DuplicateKey {}
+
+/// Maps each key of `entries` to its last value.
+fn last_values(entries: &[(String, JsonValue)]) -> HashMap<&str, &JsonValue> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.as_str(), value))
+        .collect()
+}
+
+#[cfg(feature = "indexmap")]
+impl JsonValue {
+    /// Returns the entries of an object as an insertion-ordered map, or `None` for
+    /// other values. A repeated key keeps its first position and its last value.
+    pub fn as_index_map(&self) -> Option<indexmap::IndexMap<&str, &JsonValue>> {
+        match self {
+            JsonValue::Object(entries) => Some(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JsonParserError {
+    UnexpectedToken(JsonToken),
+    UnexpectedEndOfInput,
+    /// A value was required, but the given `]`, `}`, `,` or `:` was found, as in
+    /// `[1,]`. Input that ends where a value is required is `UnexpectedEndOfInput`.
+    ExpectedValue(JsonToken),
+    /// A key was required, but the given scalar or opening bracket was found, as
+    /// in `{1: 2}`.
+    ExpectedObjectKey(JsonToken),
+    DuplicateKey(String),
+    EmptyKey,
+    /// Arrays and objects were nested more than `MAX_NESTING_DEPTH` levels deep.
+    TooDeep,
+}
 
-    tokens.next(); // consume the LeftCurlyBracket
+/// How deeply arrays and objects may nest for the parsers that recurse once per
+/// level: `parse_spanned`, `parse_prefix`, the comment-preserving parsers and
+/// `parse_recovering`. `parse` and `parser` have no such limit.
+pub const MAX_NESTING_DEPTH: usize = 512;
 
-    if let Some(&token) = tokens.peek() {
-        match token {
-            JsonToken::RightCurlyBracket => {
-                tokens.next();
-                return Ok(JsonValue::Object(object));
+impl std::fmt::Display for JsonParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JsonParserError::UnexpectedToken(token) => write!(f, "Unexpected token: '{}'", token),
+            JsonParserError::UnexpectedEndOfInput => write!(f, "Unexpected end of input"),
+            JsonParserError::ExpectedValue(token) => {
+                write!(f, "Expected a value, found '{}'", token)
             }
-            JsonToken::String(_) => {
-                let (key, value) = parser_object_key_value(&mut tokens)?;
-                object.push((key, value));
+            JsonParserError::ExpectedObjectKey(token) => {
+                write!(f, "Expected a string key, found '{}'", token)
             }
-            _ => {
-                return Err(JsonParserError::UnexpectedToken(token.clone()));
+            JsonParserError::DuplicateKey(key) => write!(f, "Duplicate key: '{}'", key),
+            JsonParserError::EmptyKey => write!(f, "Empty key"),
+            JsonParserError::TooDeep => {
+                write!(f, "Nested more than {} levels deep", MAX_NESTING_DEPTH)
             }
         }
     }
+}
 
-    while let Some(&token) = tokens.peek() {
-        match token {
-            JsonToken::Comma => {
-                tokens.next();
-                if let Some(&token) = tokens.peek() {
-                    match token {
-                        JsonToken::String(_) => {
-                            let (key, value) = parser_object_key_value(&mut tokens)?;
-                            object.push((key, value));
-                        }
-                        _ => {
-                            return Err(JsonParserError::UnexpectedToken(token.clone()));
-                        }
-                    }
+#[allow(clippy::ptr_arg)]
+pub fn parser(tokens: &Vec<JsonToken>) -> Result<JsonValue, JsonParserError> {
+    parse_tokens(tokens).map_err(|(error, _)| error)
+}
+
+/// Parses `tokens` like `parser`, returning with an error the index of the token
+/// it was found at, or `tokens.len()` if the tokens ended too early.
+pub(crate) fn parse_tokens(tokens: &[JsonToken]) -> Result<JsonValue, (JsonParserError, usize)> {
+    let mut position = 0;
+    let value = parser_value(tokens, &mut position).map_err(|error| (error, position))?;
+    if let Some(token) = tokens.get(position) {
+        return Err((JsonParserError::UnexpectedToken(token.clone()), position));
+    };
+    Ok(value)
+}
+
+/// An array or object whose elements are still being parsed.
+enum Container {
+    Array(Vec<JsonValue>),
+    /// The entries so far, and the key of the entry whose value is being parsed.
+    Object(Vec<(String, JsonValue)>, String),
+}
+
+impl Container {
+    fn push(&mut self, value: JsonValue) {
+        match self {
+            Container::Array(array) => array.push(value),
+            Container::Object(object, key) => object.push((std::mem::take(key), value)),
+        }
+    }
+
+    fn closing(&self) -> JsonToken {
+        match self {
+            Container::Array(_) => JsonToken::RightSquareBracket,
+            Container::Object(..) => JsonToken::RightCurlyBracket,
+        }
+    }
+
+    fn into_value(self) -> JsonValue {
+        match self {
+            Container::Array(array) => JsonValue::Array(array),
+            Container::Object(object, _) => JsonValue::Object(object),
+        }
+    }
+}
+
+/// Parses one value without recursing: open containers are kept on an explicit
+/// stack, so nesting depth is limited only by memory.
+///
+/// On error, `position` is left at the token the error was found at.
+fn parser_value(tokens: &[JsonToken], position: &mut usize) -> Result<JsonValue, JsonParserError> {
+    let mut stack: Vec<Container> = Vec::new();
+
+    loop {
+        let mut value = match tokens.get(*position) {
+            Some(JsonToken::Null) => JsonValue::Null,
+            Some(JsonToken::True) => JsonValue::Bool(true),
+            Some(JsonToken::False) => JsonValue::Bool(false),
+            Some(JsonToken::Number(number)) => JsonValue::Number(*number),
+            Some(JsonToken::String(string)) => JsonValue::String(string.clone()),
+            Some(JsonToken::LeftSquareBracket) => {
+                *position += 1;
+                if tokens.get(*position) == Some(&JsonToken::RightSquareBracket) {
+                    JsonValue::Array(Vec::new())
+                } else {
+                    stack.push(Container::Array(Vec::new()));
+                    continue;
                 }
             }
-            JsonToken::RightCurlyBracket => {
-                tokens.next();
-                return Ok(JsonValue::Object(object));
+            Some(JsonToken::LeftCurlyBracket) => {
+                *position += 1;
+                if tokens.get(*position) == Some(&JsonToken::RightCurlyBracket) {
+                    JsonValue::Object(Vec::new())
+                } else {
+                    let key = parser_object_key(tokens, position)?;
+                    stack.push(Container::Object(Vec::new(), key));
+                    continue;
+                }
             }
-            _ => {
-                return Err(JsonParserError::UnexpectedToken(token.clone()));
+            Some(token) => return Err(JsonParserError::ExpectedValue(token.clone())),
+            None => return Err(JsonParserError::UnexpectedEndOfInput),
+        };
+        *position += 1; // consume the scalar or the closing bracket of an empty container
+
+        loop {
+            let Some(mut container) = stack.pop() else {
+                return Ok(value);
+            };
+            container.push(value);
+
+            match tokens.get(*position) {
+                Some(JsonToken::Comma) => {
+                    *position += 1;
+                    if let Container::Object(_, key) = &mut container {
+                        *key = parser_object_key(tokens, position)?;
+                    }
+                    stack.push(container);
+                    break;
+                }
+                Some(token) if *token == container.closing() => {
+                    *position += 1;
+                    value = container.into_value();
+                }
+                Some(token) => return Err(JsonParserError::UnexpectedToken(token.clone())),
+                None => return Err(JsonParserError::UnexpectedEndOfInput),
             }
         }
     }
-
-    Err(JsonParserError::UnexpectedEndOfInput)
 }
 
-fn parser_object_key_value(
-    tokens: &mut Peekable<Iter<'_, JsonToken>>,
-) -> Result<(String, JsonValue), JsonParserError> {
-    let key = tokens.next();
-    let key = match key {
+/// Reads an object key and the colon after it.
+fn parser_object_key(
+    tokens: &[JsonToken],
+    position: &mut usize,
+) -> Result<String, JsonParserError> {
+    let key = match tokens.get(*position) {
         Some(JsonToken::String(key)) => key.clone(),
-        Some(token) => return Err(JsonParserError::UnexpectedToken(token.clone())),
+        Some(token) => return Err(unexpected_key(token)),
         None => return Err(JsonParserError::UnexpectedEndOfInput),
     };
+    *position += 1;
+
+    match tokens.get(*position) {
+        Some(JsonToken::Colon) => {
+            *position += 1;
+            Ok(key)
+        }
+        Some(token) => Err(JsonParserError::UnexpectedToken(token.clone())),
+        None => Err(JsonParserError::UnexpectedEndOfInput),
+    }
+}
 
-    let colon = tokens.next();
-    if colon != Some(&JsonToken::Colon) {
-        return Err(JsonParserError::UnexpectedEndOfInput);
+/// The error for `token` found where an object key is required.
+pub(crate) fn unexpected_key(token: &JsonToken) -> JsonParserError {
+    match token {
+        JsonToken::RightSquareBracket
+        | JsonToken::RightCurlyBracket
+        | JsonToken::Colon
+        | JsonToken::Comma => JsonParserError::UnexpectedToken(token.clone()),
+        _ => JsonParserError::ExpectedObjectKey(token.clone()),
     }
+}
 
-    let value = parser_value(tokens)?;
+/// Parses `tokens`, recovering from structural errors instead of stopping at the first.
+///
+/// When an array element or object entry can't be parsed, the error is recorded and
+/// tokens are skipped up to the next `,` or closing bracket of the same container.
+/// The returned value contains everything that could be parsed.
+pub fn parse_recovering(tokens: &[JsonToken]) -> (Option<JsonValue>, Vec<JsonParserError>) {
+    let mut recovery = Recovery {
+        tokens,
+        position: 0,
+        depth: 0,
+        errors: Vec::new(),
+        last_error_position: None,
+    };
+    let value = recovery.value();
+    if recovery.position < tokens.len() {
+        recovery.error();
+    }
+    (value, recovery.errors)
+}
 
-    Ok((key, value))
+struct Recovery<'a> {
+    tokens: &'a [JsonToken],
+    position: usize,
+    /// The number of arrays and objects the current position is nested in.
+    depth: usize,
+    errors: Vec<JsonParserError>,
+    last_error_position: Option<usize>,
 }
 
-fn parser_array(
-    mut tokens: &mut Peekable<Iter<'_, JsonToken>>,
-) -> Result<JsonValue, JsonParserError> {
-    let mut array = Vec::new();
+impl Recovery<'_> {
+    fn peek(&self) -> Option<&JsonToken> {
+        self.tokens.get(self.position)
+    }
 
-    tokens.next(); // consume the LeftSquareBracket
+    /// Records an error for the current token, once per position.
+    fn error(&mut self) {
+        self.error_for(|token| JsonParserError::UnexpectedToken(token.clone()));
+    }
 
-    if let Some(&token) = tokens.peek() {
-        match token {
-            JsonToken::RightSquareBracket => {
-                tokens.next();
-                return Ok(JsonValue::Array(array));
+    /// Records the error `error` gives for the current token, like `error`.
+    fn error_for(&mut self, error: fn(&JsonToken) -> JsonParserError) {
+        if self.last_error_position == Some(self.position) {
+            return;
+        }
+        self.last_error_position = Some(self.position);
+        self.errors.push(match self.peek() {
+            Some(token) => error(token),
+            None => JsonParserError::UnexpectedEndOfInput,
+        });
+    }
+
+    /// Skips to the next `,`, `]` or `}` that isn't nested deeper than the current container.
+    fn skip_to_delimiter(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            match token {
+                JsonToken::LeftSquareBracket | JsonToken::LeftCurlyBracket => depth += 1,
+                JsonToken::RightSquareBracket | JsonToken::RightCurlyBracket if depth > 0 => {
+                    depth -= 1
+                }
+                JsonToken::Comma | JsonToken::RightSquareBracket | JsonToken::RightCurlyBracket => {
+                    return
+                }
+                _ => {}
+            }
+            self.position += 1;
+        }
+    }
+
+    fn value(&mut self) -> Option<JsonValue> {
+        let value = match self.peek() {
+            Some(JsonToken::Null) => JsonValue::Null,
+            Some(JsonToken::True) => JsonValue::Bool(true),
+            Some(JsonToken::False) => JsonValue::Bool(false),
+            Some(JsonToken::Number(number)) => JsonValue::Number(*number),
+            Some(JsonToken::String(string)) => JsonValue::String(string.clone()),
+            Some(JsonToken::LeftSquareBracket | JsonToken::LeftCurlyBracket)
+                if self.depth == MAX_NESTING_DEPTH =>
+            {
+                self.errors.push(JsonParserError::TooDeep);
+                self.last_error_position = Some(self.position);
+                self.skip_container();
+                return None;
             }
+            Some(JsonToken::LeftSquareBracket) => return Some(self.nested(Self::array)),
+            Some(JsonToken::LeftCurlyBracket) => return Some(self.nested(Self::object)),
             _ => {
-                let value = parser_value(&mut tokens)?;
-                array.push(value);
+                self.error_for(|token| JsonParserError::ExpectedValue(token.clone()));
+                return None;
+            }
+        };
+        self.position += 1;
+        Some(value)
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> JsonValue) -> JsonValue {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Skips the array or object starting at the current token, including
+    /// everything nested in it.
+    fn skip_container(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            match token {
+                JsonToken::LeftSquareBracket | JsonToken::LeftCurlyBracket => depth += 1,
+                JsonToken::RightSquareBracket | JsonToken::RightCurlyBracket => depth -= 1,
+                _ => {}
+            }
+            self.position += 1;
+            if depth == 0 {
+                return;
             }
         }
-    };
+    }
+
+    fn array(&mut self) -> JsonValue {
+        let mut array = Vec::new();
+
+        self.position += 1; // consume the LeftSquareBracket
+
+        if self.peek() == Some(&JsonToken::RightSquareBracket) {
+            self.position += 1;
+            return JsonValue::Array(array);
+        }
+
+        loop {
+            match self.value() {
+                Some(value) => array.push(value),
+                None => self.skip_to_delimiter(),
+            }
+            if self.delimiter(&JsonToken::RightSquareBracket) {
+                return JsonValue::Array(array);
+            }
+        }
+    }
+
+    fn object(&mut self) -> JsonValue {
+        let mut object = Vec::new();
 
-    while let Some(&token) = tokens.peek() {
-        match token {
-            JsonToken::Comma => {
-                tokens.next();
-                let value = parser_value(&mut tokens)?;
-                array.push(value);
+        self.position += 1; // consume the LeftCurlyBracket
+
+        if self.peek() == Some(&JsonToken::RightCurlyBracket) {
+            self.position += 1;
+            return JsonValue::Object(object);
+        }
+
+        loop {
+            match self.entry() {
+                Some(entry) => object.push(entry),
+                None => self.skip_to_delimiter(),
             }
-            JsonToken::RightSquareBracket => {
-                tokens.next();
-                return Ok(JsonValue::Array(array));
+            if self.delimiter(&JsonToken::RightCurlyBracket) {
+                return JsonValue::Object(object);
             }
+        }
+    }
+
+    fn entry(&mut self) -> Option<(String, JsonValue)> {
+        let key = match self.peek() {
+            Some(JsonToken::String(key)) => key.clone(),
             _ => {
-                return Err(JsonParserError::UnexpectedToken(token.clone()));
+                self.error_for(unexpected_key);
+                return None;
             }
+        };
+        self.position += 1;
+
+        if self.peek() != Some(&JsonToken::Colon) {
+            self.error();
+            return None;
         }
+        self.position += 1;
+
+        self.value().map(|value| (key, value))
     }
 
-    Err(JsonParserError::UnexpectedEndOfInput)
+    /// Consumes the `,` after an element and returns `false`, or consumes the
+    /// closing bracket and returns `true`. Anything else is reported and skipped.
+    fn delimiter(&mut self, closing: &JsonToken) -> bool {
+        loop {
+            match self.peek() {
+                Some(JsonToken::Comma) => {
+                    self.position += 1;
+                    return false;
+                }
+                Some(token) if token == closing => {
+                    self.position += 1;
+                    return true;
+                }
+                Some(_) => {
+                    self.error();
+                    self.position += 1;
+                    self.skip_to_delimiter();
+                }
+                None => {
+                    self.error();
+                    return true;
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -199,9 +610,9 @@ mod tests {
 
     #[test]
     fn parse_number() {
-        let tokens = vec![JsonToken::Number(42.0)];
+        let tokens = vec![JsonToken::Number(JsonNumber::Integer(42))];
         let result = parser(&tokens);
-        assert_eq!(result, Ok(JsonValue::Number(42.0)));
+        assert_eq!(result, Ok(JsonValue::Number(JsonNumber::Integer(42))));
     }
 
     #[test]
@@ -230,7 +641,7 @@ mod tests {
             JsonToken::Comma,
             JsonToken::String("hello".to_string()),
             JsonToken::Comma,
-            JsonToken::Number(42.0),
+            JsonToken::Number(JsonNumber::Integer(42)),
             JsonToken::RightSquareBracket,
         ];
         let result = parser(&tokens);
@@ -241,7 +652,7 @@ mod tests {
                 JsonValue::Bool(true),
                 JsonValue::Bool(false),
                 JsonValue::String("hello".to_string()),
-                JsonValue::Number(42.0),
+                JsonValue::Number(JsonNumber::Integer(42)),
             ]))
         );
     }
@@ -275,7 +686,7 @@ mod tests {
             JsonToken::Comma,
             JsonToken::String("number".to_string()),
             JsonToken::Colon,
-            JsonToken::Number(42.0),
+            JsonToken::Number(JsonNumber::Integer(42)),
             JsonToken::RightCurlyBracket,
         ];
         let result = parser(&tokens);
@@ -286,7 +697,10 @@ mod tests {
                 ("true".to_string(), JsonValue::Bool(true)),
                 ("false".to_string(), JsonValue::Bool(false)),
                 ("string".to_string(), JsonValue::String("hello".to_string())),
-                ("number".to_string(), JsonValue::Number(42.0))
+                (
+                    "number".to_string(),
+                    JsonValue::Number(JsonNumber::Integer(42))
+                )
             ]))
         );
     }
@@ -309,7 +723,7 @@ mod tests {
             JsonToken::String("array".to_string()),
             JsonToken::Colon,
             JsonToken::LeftSquareBracket,
-            JsonToken::Number(42.0),
+            JsonToken::Number(JsonNumber::Integer(42)),
             JsonToken::RightSquareBracket,
             JsonToken::RightCurlyBracket,
             JsonToken::RightCurlyBracket,
@@ -325,11 +739,356 @@ mod tests {
                         ("null".to_string(), JsonValue::Null),
                         (
                             "array".to_string(),
-                            JsonValue::Array(vec![JsonValue::Number(42.0)])
+                            JsonValue::Array(vec![JsonValue::Number(JsonNumber::Integer(42))])
                         )
                     ]),
                 )
             ]))
         );
     }
+
+    #[test]
+    fn parse_recovering_reports_multiple_errors() {
+        // {"a": 1 2, "b": , "c": 3}
+        let tokens = vec![
+            JsonToken::LeftCurlyBracket,
+            JsonToken::String("a".to_string()),
+            JsonToken::Colon,
+            JsonToken::Number(JsonNumber::Integer(1)),
+            JsonToken::Number(JsonNumber::Integer(2)),
+            JsonToken::Comma,
+            JsonToken::String("b".to_string()),
+            JsonToken::Colon,
+            JsonToken::Comma,
+            JsonToken::String("c".to_string()),
+            JsonToken::Colon,
+            JsonToken::Number(JsonNumber::Integer(3)),
+            JsonToken::RightCurlyBracket,
+        ];
+        let (value, errors) = parse_recovering(&tokens);
+        assert_eq!(
+            value,
+            Some(JsonValue::Object(vec![
+                ("a".to_string(), JsonValue::Number(JsonNumber::Integer(1))),
+                ("c".to_string(), JsonValue::Number(JsonNumber::Integer(3))),
+            ]))
+        );
+        assert_eq!(
+            errors,
+            vec![
+                JsonParserError::UnexpectedToken(JsonToken::Number(JsonNumber::Integer(2))),
+                JsonParserError::ExpectedValue(JsonToken::Comma),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_recovering_nested_and_unterminated() {
+        // [[1 true], }
+        let tokens = vec![
+            JsonToken::LeftSquareBracket,
+            JsonToken::LeftSquareBracket,
+            JsonToken::Number(JsonNumber::Integer(1)),
+            JsonToken::True,
+            JsonToken::RightSquareBracket,
+            JsonToken::Comma,
+            JsonToken::RightCurlyBracket,
+        ];
+        let (value, errors) = parse_recovering(&tokens);
+        assert_eq!(
+            value,
+            Some(JsonValue::Array(vec![JsonValue::Array(vec![
+                JsonValue::Number(JsonNumber::Integer(1))
+            ])]))
+        );
+        assert_eq!(
+            errors,
+            vec![
+                JsonParserError::UnexpectedToken(JsonToken::True),
+                JsonParserError::ExpectedValue(JsonToken::RightCurlyBracket),
+                JsonParserError::UnexpectedEndOfInput,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_recovering_reports_errors_like_parser() {
+        for content in ["[1,]", "{2: 3}", "{\"a\": 1, true: 2}", "[1, :]"] {
+            let tokens = crate::tokenizer::tokenize(content).unwrap();
+            let (_, errors) = parse_recovering(&tokens);
+            assert_eq!(errors, vec![parser(&tokens).unwrap_err()], "{}", content);
+        }
+    }
+
+    #[test]
+    fn parse_recovering_depth_limit() {
+        // [[[...]]], 1]
+        let depth = MAX_NESTING_DEPTH + 10;
+        let mut tokens = vec![JsonToken::LeftSquareBracket];
+        tokens.extend(std::iter::repeat_n(JsonToken::LeftSquareBracket, depth));
+        tokens.extend(std::iter::repeat_n(JsonToken::RightSquareBracket, depth));
+        tokens.extend([
+            JsonToken::Comma,
+            JsonToken::Number(JsonNumber::Integer(1)),
+            JsonToken::RightSquareBracket,
+        ]);
+
+        let (value, errors) = parse_recovering(&tokens);
+        assert_eq!(errors, vec![JsonParserError::TooDeep]);
+        let Some(JsonValue::Array(values)) = &value else {
+            panic!("expected an array");
+        };
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[1], JsonValue::Number(JsonNumber::Integer(1)));
+    }
+
+    #[test]
+    fn parse_recovering_valid_input() {
+        let tokens = vec![
+            JsonToken::LeftSquareBracket,
+            JsonToken::Null,
+            JsonToken::RightSquareBracket,
+        ];
+        assert_eq!(
+            parse_recovering(&tokens),
+            (Some(JsonValue::Array(vec![JsonValue::Null])), vec![])
+        );
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn as_index_map_preserves_key_order() {
+        let value = crate::parse(r#"{"z": 1, "a": 2, "m": 3, "a": 4}"#).unwrap();
+        let map = value.as_index_map().unwrap();
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec!["z", "a", "m"]);
+        assert_eq!(
+            map.get("a"),
+            Some(&&JsonValue::Number(JsonNumber::Integer(4)))
+        );
+        assert_eq!(map.get_index_of("m"), Some(2));
+        assert_eq!(map.get("missing"), None);
+        assert_eq!(JsonValue::Null.as_index_map(), None);
+    }
+
+    #[test]
+    fn parse_deep_nesting_without_recursion() {
+        let depth = 200_000;
+        let tokens: Vec<JsonToken> = std::iter::repeat_n(JsonToken::LeftSquareBracket, depth)
+            .chain(std::iter::repeat_n(JsonToken::RightSquareBracket, depth))
+            .collect();
+
+        let value = parser(&tokens).unwrap();
+        let mut levels = 1;
+        let mut inner = &value;
+        while let JsonValue::Array(array) = inner {
+            match array.first() {
+                Some(first) => inner = first,
+                None => break,
+            }
+            levels += 1;
+        }
+        assert_eq!(levels, depth);
+        value.drop_deep();
+    }
+
+    #[test]
+    fn parse_matches_recursive_spanned_parser() {
+        let content =
+            r#"{"a": [1, {"b": null, "c": [true, false, []]}, {}], "d": "e", "f": {"g": -2.5}}"#;
+        let tokens = crate::tokenizer::tokenize(content).unwrap();
+        assert_eq!(
+            parser(&tokens),
+            Ok(crate::spanned::parse_spanned(content).unwrap().to_value())
+        );
+    }
+
+    #[test]
+    fn semantic_eq_ignores_key_order() {
+        let a = crate::parse(r#"{"a": 1, "b": [{"c": 2, "d": 3}]}"#).unwrap();
+        let b = crate::parse(r#"{"b": [{"d": 3, "c": 2}], "a": 1}"#).unwrap();
+        assert_ne!(a, b);
+        assert!(a.semantic_eq(&b));
+        assert!(b.semantic_eq(&a));
+
+        let c = crate::parse(r#"{"a": 1, "b": [{"c": 2}]}"#).unwrap();
+        assert!(!a.semantic_eq(&c));
+        assert!(!c.semantic_eq(&a));
+    }
+
+    #[test]
+    fn semantic_eq_numbers_and_duplicate_keys() {
+        assert!(JsonValue::Number(JsonNumber::Float(0.1 + 0.2))
+            .semantic_eq(&JsonValue::Number(JsonNumber::Float(0.3))));
+        assert!(!JsonValue::Number(JsonNumber::Integer(1))
+            .semantic_eq(&JsonValue::Number(JsonNumber::Float(1.001))));
+        assert!(!JsonValue::Array(vec![JsonValue::Null]).semantic_eq(&JsonValue::Array(vec![])));
+
+        let a = crate::parse(r#"{"a": 1, "a": 2}"#).unwrap();
+        let b = crate::parse(r#"{"a": 2}"#).unwrap();
+        assert!(a.semantic_eq(&b));
+    }
+
+    #[test]
+    fn semantic_eq_large_objects() {
+        let entries: Vec<(String, JsonValue)> = (0..100_000)
+            .map(|n| (n.to_string(), JsonValue::Number(JsonNumber::Integer(n))))
+            .collect();
+        let a = JsonValue::Object(entries.clone());
+        let b = JsonValue::Object(entries.into_iter().rev().collect());
+        assert!(a.semantic_eq(&b));
+    }
+
+    #[test]
+    fn object_checked_accepts_unique_keys() {
+        let entries = vec![
+            ("a".to_string(), JsonValue::Null),
+            ("b".to_string(), crate::parse(r#"{"a": 1}"#).unwrap()),
+        ];
+        assert_eq!(
+            JsonValue::object_checked(entries.clone()),
+            Ok(JsonValue::Object(entries))
+        );
+        assert_eq!(
+            JsonValue::object_checked(vec![]),
+            Ok(JsonValue::Object(vec![]))
+        );
+    }
+
+    #[test]
+    fn object_checked_rejects_duplicate_key() {
+        let error = JsonValue::object_checked(vec![
+            ("a".to_string(), JsonValue::Null),
+            ("b".to_string(), JsonValue::Bool(true)),
+            ("a".to_string(), JsonValue::Bool(false)),
+        ])
+        .unwrap_err();
+        assert_eq!(error, DuplicateKey("a".to_string()));
+        assert_eq!(error.to_string(), "Duplicate key: 'a'");
+    }
+
+    #[test]
+    fn equal_values_hash_equally() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+
+        let hash = |value: &JsonValue| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        let a = crate::parse(r#"{"a": [1, 2.5, "x", null], "b": {"c": true}}"#).unwrap();
+        let b = crate::parse(r#"{"a": [1, 2.5, "x", null], "b": {"c": true}}"#).unwrap();
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(
+            hash(&JsonValue::Number(JsonNumber::Float(0.0))),
+            hash(&JsonValue::Number(JsonNumber::Float(-0.0)))
+        );
+
+        let values = crate::parse(r#"[{"a": 1}, [1], {"a": 1}, {"a": 1.0}, [1], -0.0, 0.0]"#);
+        let values = values.unwrap();
+        let JsonValue::Array(values) = &values else {
+            panic!("expected an array");
+        };
+        let unique: HashSet<JsonValue> = values.iter().cloned().collect();
+        assert_eq!(unique.len(), 4);
+        assert!(unique.contains(&crate::parse(r#"{"a": 1}"#).unwrap()));
+        assert!(unique.contains(&crate::parse(r#"{"a": 1.0}"#).unwrap()));
+    }
+
+    #[test]
+    fn nan_is_equal_to_itself() {
+        use std::collections::HashSet;
+
+        let nan = JsonValue::Number(JsonNumber::from(f64::NAN));
+        let other_nan = JsonValue::Number(JsonNumber::Float(-f64::NAN));
+        assert_eq!(nan, nan.clone());
+        assert_eq!(nan, other_nan);
+        assert_ne!(nan, JsonValue::Number(JsonNumber::Float(0.0)));
+
+        let unique: HashSet<JsonValue> =
+            [nan.clone(), other_nan, nan.clone()].into_iter().collect();
+        assert_eq!(unique.len(), 1);
+        assert!(unique.contains(&nan));
+    }
+
+    #[test]
+    fn parse_expected_value() {
+        let parse = |content| parser(&crate::tokenizer::tokenize(content).unwrap());
+        assert_eq!(
+            parse("[1,]"),
+            Err(JsonParserError::ExpectedValue(
+                JsonToken::RightSquareBracket
+            ))
+        );
+        assert_eq!(
+            parse("[,1]"),
+            Err(JsonParserError::ExpectedValue(JsonToken::Comma))
+        );
+        assert_eq!(
+            parse(r#"{"a": }"#),
+            Err(JsonParserError::ExpectedValue(JsonToken::RightCurlyBracket))
+        );
+        assert_eq!(parse("[1,"), Err(JsonParserError::UnexpectedEndOfInput));
+        assert_eq!(
+            parse("[1 2]"),
+            Err(JsonParserError::UnexpectedToken(JsonToken::Number(
+                JsonNumber::Integer(2)
+            )))
+        );
+        assert_eq!(
+            JsonParserError::ExpectedValue(JsonToken::Comma).to_string(),
+            "Expected a value, found ','"
+        );
+    }
+
+    #[test]
+    fn parse_expected_object_key() {
+        let parse = |content| parser(&crate::tokenizer::tokenize(content).unwrap());
+        assert_eq!(
+            parse("{1:2}"),
+            Err(JsonParserError::ExpectedObjectKey(JsonToken::Number(
+                JsonNumber::Integer(1)
+            )))
+        );
+        assert_eq!(
+            parse("{true:1}"),
+            Err(JsonParserError::ExpectedObjectKey(JsonToken::True))
+        );
+        assert_eq!(
+            parse(r#"{"a": 1, [2]: 3}"#),
+            Err(JsonParserError::ExpectedObjectKey(
+                JsonToken::LeftSquareBracket
+            ))
+        );
+        assert_eq!(
+            parse(r#"{"a": 1,}"#),
+            Err(JsonParserError::UnexpectedToken(
+                JsonToken::RightCurlyBracket
+            ))
+        );
+        assert_eq!(
+            JsonParserError::ExpectedObjectKey(JsonToken::True).to_string(),
+            "Expected a string key, found 'true'"
+        );
+    }
+
+    #[test]
+    fn parse_object_missing_colon() {
+        let parse = |content| parser(&crate::tokenizer::tokenize(content).unwrap());
+        assert_eq!(
+            parse(r#"{"a" 1}"#),
+            Err(JsonParserError::UnexpectedToken(JsonToken::Number(
+                JsonNumber::Integer(1)
+            )))
+        );
+        assert_eq!(
+            parse(r#"{"a"::1}"#),
+            Err(JsonParserError::ExpectedValue(JsonToken::Colon))
+        );
+        assert_eq!(
+            parse(r#"{"a":"#),
+            Err(JsonParserError::UnexpectedEndOfInput)
+        );
+        assert_eq!(parse(r#"{"a""#), Err(JsonParserError::UnexpectedEndOfInput));
+    }
 }
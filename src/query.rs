@@ -0,0 +1,318 @@
+use crate::parser::JsonValue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LeafKind {
+    String,
+    Number,
+    Bool,
+    Null,
+}
+
+/// Returns every scalar in `value` with its JSON Pointer path and kind, in document order.
+///
+/// Empty arrays and objects hold no scalars and therefore produce no leaves.
+pub fn leaves(value: &JsonValue) -> Vec<(String, LeafKind, &JsonValue)> {
+    let mut leaves = Vec::new();
+    collect_leaves(value, &mut String::new(), &mut leaves);
+    leaves
+}
+
+fn collect_leaves<'a>(
+    value: &'a JsonValue,
+    path: &mut String,
+    leaves: &mut Vec<(String, LeafKind, &'a JsonValue)>,
+) {
+    let kind = match value {
+        JsonValue::Null => LeafKind::Null,
+        JsonValue::Bool(_) => LeafKind::Bool,
+        JsonValue::Number(_) => LeafKind::Number,
+        JsonValue::String(_) => LeafKind::String,
+        JsonValue::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                collect_leaves(value, path, leaves);
+                path.truncate(len);
+            }
+            return;
+        }
+        JsonValue::Object(entries) => {
+            for (key, value) in entries {
+                let len = path.len();
+                push_segment(path, key);
+                collect_leaves(value, path, leaves);
+                path.truncate(len);
+            }
+            return;
+        }
+    };
+    leaves.push((path.clone(), kind, value));
+}
+
+/// Counts the values in `value`, itself included, that are equal to `needle`.
+///
+/// Values are compared with `==`, so numbers must match exactly, including
+/// whether they are integers, and objects must have their keys in the same order.
+pub fn count_value(value: &JsonValue, needle: &JsonValue) -> usize {
+    let nested = match value {
+        JsonValue::Array(values) => values.iter().map(|value| count_value(value, needle)).sum(),
+        JsonValue::Object(entries) => entries
+            .iter()
+            .map(|(_, value)| count_value(value, needle))
+            .sum(),
+        _ => 0,
+    };
+    nested + usize::from(value == needle)
+}
+
+/// Returns the JSON Pointer of the first value in `value`, itself included, for
+/// which `predicate` holds. Containers are checked before their elements, which
+/// are searched in document order.
+pub fn find_path(value: &JsonValue, predicate: impl Fn(&JsonValue) -> bool) -> Option<String> {
+    let mut path = String::new();
+    find_path_from(value, &mut path, &predicate).then_some(path)
+}
+
+/// Leaves `path` pointing at the first match and returns true, if there is one.
+fn find_path_from(
+    value: &JsonValue,
+    path: &mut String,
+    predicate: &impl Fn(&JsonValue) -> bool,
+) -> bool {
+    if predicate(value) {
+        return true;
+    }
+    match value {
+        JsonValue::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                if find_path_from(value, path, predicate) {
+                    return true;
+                }
+                path.truncate(len);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (key, value) in entries {
+                let len = path.len();
+                push_segment(path, key);
+                if find_path_from(value, path, predicate) {
+                    return true;
+                }
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Which entry a lookup resolves to when an object repeats a key.
+///
+/// The default is `Last`, the entry most JSON parsers keep. The typed getters
+/// such as `get_str`, `semantic_eq` and the canonical form resolve a repeated
+/// key to its last entry too.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum KeyMatch {
+    /// The first entry with the key, in document order.
+    First,
+    /// The last entry with the key.
+    #[default]
+    Last,
+}
+
+impl JsonValue {
+    /// Returns the value at the RFC 6901 JSON Pointer `pointer`, such as
+    /// `/servers/0/port`, or `None` if there is none. The empty pointer refers to
+    /// the value itself. A key repeated in an object resolves as per
+    /// `KeyMatch::default`, to its last entry.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        self.pointer_with(pointer, KeyMatch::default())
+    }
+
+    /// Like `pointer`, with `key_match` choosing the entry of a repeated key.
+    pub fn pointer_with(&self, pointer: &str, key_match: KeyMatch) -> Option<&JsonValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        let mut value = self;
+        for segment in pointer.strip_prefix('/')?.split('/') {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            value = match value {
+                JsonValue::Object(entries) => {
+                    let mut matches = entries.iter().filter(|(key, _)| *key == segment);
+                    let entry = match key_match {
+                        KeyMatch::First => matches.next(),
+                        KeyMatch::Last => matches.next_back(),
+                    };
+                    &entry?.1
+                }
+                JsonValue::Array(values) => {
+                    let leading_zero = segment.len() > 1 && segment.starts_with('0');
+                    if leading_zero || !segment.bytes().all(|byte| byte.is_ascii_digit()) {
+                        return None;
+                    }
+                    values.get(segment.parse::<usize>().ok()?)?
+                }
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+/// Appends `/segment` to a JSON Pointer, escaping `~` and `/` as per RFC 6901.
+pub(crate) fn push_segment(path: &mut String, segment: &str) {
+    path.push('/');
+    for char in segment.chars() {
+        match char {
+            '~' => path.push_str("~0"),
+            '/' => path.push_str("~1"),
+            _ => path.push(char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonNumber;
+
+    #[test]
+    fn leaves_of_nested_document() {
+        let value = crate::parse(
+            r#"{"name": "a", "tags": ["x", 1], "meta": {"ok": true, "a/b": null, "empty": []}}"#,
+        )
+        .unwrap();
+        let leaves: Vec<(String, LeafKind, JsonValue)> = leaves(&value)
+            .into_iter()
+            .map(|(path, kind, value)| (path, kind, value.clone()))
+            .collect();
+        assert_eq!(
+            leaves,
+            vec![
+                (
+                    "/name".to_string(),
+                    LeafKind::String,
+                    JsonValue::String("a".to_string())
+                ),
+                (
+                    "/tags/0".to_string(),
+                    LeafKind::String,
+                    JsonValue::String("x".to_string())
+                ),
+                (
+                    "/tags/1".to_string(),
+                    LeafKind::Number,
+                    JsonValue::Number(JsonNumber::Integer(1))
+                ),
+                (
+                    "/meta/ok".to_string(),
+                    LeafKind::Bool,
+                    JsonValue::Bool(true)
+                ),
+                ("/meta/a~1b".to_string(), LeafKind::Null, JsonValue::Null),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_of_scalar() {
+        let value = JsonValue::Number(JsonNumber::Integer(1));
+        assert_eq!(
+            leaves(&value),
+            vec![("".to_string(), LeafKind::Number, &value)]
+        );
+    }
+
+    #[test]
+    fn count_value_in_nested_document() {
+        let value = crate::parse(
+            r#"{"a": null, "b": [null, "N/A", {"c": null, "d": "N/A"}], "N/A": [1, 1.0, [1]]}"#,
+        )
+        .unwrap();
+        assert_eq!(count_value(&value, &JsonValue::Null), 3);
+        assert_eq!(
+            count_value(&value, &JsonValue::String("N/A".to_string())),
+            2
+        );
+        assert_eq!(
+            count_value(&value, &JsonValue::Number(JsonNumber::Integer(1))),
+            2
+        );
+        assert_eq!(count_value(&value, &crate::parse("[1]").unwrap()), 1);
+        assert_eq!(count_value(&value, &JsonValue::Bool(false)), 0);
+        assert_eq!(count_value(&value, &value), 1);
+    }
+
+    #[test]
+    fn find_path_of_first_match() {
+        let value = crate::parse(
+            r#"{"users": [{"name": "ann", "age": 31}, {"name": "bob", "age": 72}], "a/b": "bob"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            find_path(&value, |value| *value
+                == JsonValue::String("bob".to_string())),
+            Some("/users/1/name".to_string())
+        );
+        let over_65 =
+            |value: &JsonValue| matches!(value, JsonValue::Number(n) if n.as_f64() > 65.0);
+        assert_eq!(find_path(&value, over_65), Some("/users/1/age".to_string()));
+        assert_eq!(
+            find_path(
+                &value,
+                |value| matches!(value, JsonValue::String(s) if s.is_empty())
+            ),
+            None
+        );
+        assert_eq!(find_path(&value, |_| true), Some(String::new()));
+    }
+
+    #[test]
+    fn pointer_resolves_paths() {
+        let value = crate::parse(r#"{"a": [10, {"b/c": true, "~": null}], "": 1}"#).unwrap();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/a/0"),
+            Some(&JsonValue::Number(JsonNumber::Integer(10)))
+        );
+        assert_eq!(value.pointer("/a/1/b~1c"), Some(&JsonValue::Bool(true)));
+        assert_eq!(value.pointer("/a/1/~0"), Some(&JsonValue::Null));
+        assert_eq!(
+            value.pointer("/"),
+            Some(&JsonValue::Number(JsonNumber::Integer(1)))
+        );
+        for missing in ["a", "/b", "/a/2", "/a/01", "/a/-", "/a/0/x"] {
+            assert_eq!(value.pointer(missing), None, "{:?} resolved", missing);
+        }
+    }
+
+    #[test]
+    fn pointer_with_duplicate_keys() {
+        let value =
+            crate::parse(r#"{"a": 1, "b": [{"c": "first", "c": "last"}], "a": 2}"#).unwrap();
+        assert_eq!(
+            value.pointer_with("/a", KeyMatch::First),
+            Some(&JsonValue::Number(JsonNumber::Integer(1)))
+        );
+        assert_eq!(
+            value.pointer_with("/a", KeyMatch::Last),
+            Some(&JsonValue::Number(JsonNumber::Integer(2)))
+        );
+        assert_eq!(
+            value.pointer("/b/0/c"),
+            Some(&JsonValue::String("last".to_string()))
+        );
+        assert_eq!(
+            value.pointer("/a"),
+            Some(&JsonValue::Number(JsonNumber::Integer(2)))
+        );
+        assert_eq!(value.get_i64("a").ok(), Some(2));
+        assert_eq!(
+            value.pointer_with("/b/0/c", KeyMatch::Last),
+            Some(&JsonValue::String("last".to_string()))
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+
+use crate::parser::JsonValue;
+use crate::query::push_segment;
+
+/// The keys an object may contain, and optionally the keys allowed in the value
+/// of each of them.
+///
+/// A key added with `key` accepts any value; one added with `nested` checks its
+/// value (or each element, if the value is an array) against another schema.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeySchema {
+    keys: Vec<(String, Option<KeySchema>)>,
+}
+
+impl KeySchema {
+    pub fn new() -> Self {
+        KeySchema::default()
+    }
+
+    /// Allows `key` with any value.
+    pub fn key(mut self, key: &str) -> Self {
+        self.keys.push((key.to_string(), None));
+        self
+    }
+
+    /// Allows `key`, whose value must in turn match `schema`.
+    pub fn nested(mut self, key: &str, schema: KeySchema) -> Self {
+        self.keys.push((key.to_string(), Some(schema)));
+        self
+    }
+
+    fn get(&self, key: &str) -> Option<&Option<KeySchema>> {
+        self.keys
+            .iter()
+            .find(|(allowed, _)| allowed == key)
+            .map(|(_, schema)| schema)
+    }
+}
+
+/// Checks every object in `value` reached through `allowed` for keys it doesn't
+/// permit, and returns the JSON Pointer of each unexpected key in document order.
+///
+/// Arrays are transparent: the schema applies to each of their elements.
+pub fn validate_keys(value: &JsonValue, allowed: &KeySchema) -> Result<(), Vec<String>> {
+    let mut unexpected = Vec::new();
+    collect_unexpected(value, allowed, &mut String::new(), &mut unexpected);
+    if unexpected.is_empty() {
+        Ok(())
+    } else {
+        Err(unexpected)
+    }
+}
+
+fn collect_unexpected(
+    value: &JsonValue,
+    schema: &KeySchema,
+    path: &mut String,
+    unexpected: &mut Vec<String>,
+) {
+    match value {
+        JsonValue::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                collect_unexpected(value, schema, path, unexpected);
+                path.truncate(len);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (key, value) in entries {
+                let len = path.len();
+                push_segment(path, key);
+                match schema.get(key) {
+                    Some(Some(nested)) => collect_unexpected(value, nested, path, unexpected),
+                    Some(None) => {}
+                    None => unexpected.push(path.clone()),
+                }
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns every key of every object in `value`, at any depth.
+pub fn collect_keys(value: &JsonValue) -> BTreeSet<String> {
+    let mut keys = BTreeSet::new();
+    visit_keys(value, &mut String::new(), &mut |key, _| {
+        if !keys.contains(key) {
+            keys.insert(key.to_string());
+        }
+    });
+    keys
+}
+
+/// Like `collect_keys`, but returns the JSON Pointer of each key, such as
+/// `/servers/0/port`, so the same key in different places is listed once per place.
+pub fn collect_key_paths(value: &JsonValue) -> BTreeSet<String> {
+    let mut paths = BTreeSet::new();
+    visit_keys(value, &mut String::new(), &mut |_, path| {
+        paths.insert(path.to_string());
+    });
+    paths
+}
+
+/// Calls `visit` with each key in `value` and its pointer, in document order.
+fn visit_keys(value: &JsonValue, path: &mut String, visit: &mut impl FnMut(&str, &str)) {
+    match value {
+        JsonValue::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                visit_keys(value, path, visit);
+                path.truncate(len);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (key, value) in entries {
+                let len = path.len();
+                push_segment(path, key);
+                visit(key, path);
+                visit_keys(value, path, visit);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_schema() -> KeySchema {
+        KeySchema::new()
+            .key("name")
+            .nested("listeners", KeySchema::new().key("host").key("port"))
+    }
+
+    #[test]
+    fn validate_keys_reports_unexpected_keys() {
+        let value = crate::parse(
+            r#"{"name": "a", "listeners": [{"host": "x", "prot": 80}], "debug": true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            validate_keys(&value, &server_schema()),
+            Err(vec!["/listeners/0/prot".to_string(), "/debug".to_string()])
+        );
+    }
+
+    #[test]
+    fn validate_keys_allowed() {
+        let value = crate::parse(
+            r#"{"name": {"any": "thing"}, "listeners": [{"host": "x", "port": 80}, {}]}"#,
+        )
+        .unwrap();
+        assert_eq!(validate_keys(&value, &server_schema()), Ok(()));
+    }
+
+    #[test]
+    fn collect_keys_nested() {
+        let value = crate::parse(
+            r#"{"name": "a", "listeners": [{"host": "x", "port": 80}, {"host": "y", "tls": {"name": "z"}}], "a/b": null}"#,
+        )
+        .unwrap();
+        let keys: Vec<String> = collect_keys(&value).into_iter().collect();
+        assert_eq!(keys, ["a/b", "host", "listeners", "name", "port", "tls"]);
+
+        let paths: Vec<String> = collect_key_paths(&value).into_iter().collect();
+        assert_eq!(
+            paths,
+            [
+                "/a~1b",
+                "/listeners",
+                "/listeners/0/host",
+                "/listeners/0/port",
+                "/listeners/1/host",
+                "/listeners/1/tls",
+                "/listeners/1/tls/name",
+                "/name",
+            ]
+        );
+        assert!(collect_keys(&crate::parse("[1, []]").unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::error::{Error, Position};
+use crate::parser::{unexpected_key, JsonNumber, JsonParserError, JsonValue, MAX_NESTING_DEPTH};
+use crate::tokenizer::{tokenize_located, JsonToken, Span, SpannedToken, TokenizeOptions};
+
+/// A parsed value together with the byte span it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedValue {
+    pub kind: SpannedKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedKind {
+    Null,
+    Bool(bool),
+    Number(JsonNumber),
+    String(String),
+    Array(Vec<SpannedValue>),
+    Object(Vec<SpannedEntry>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedEntry {
+    pub key: String,
+    pub key_span: Span,
+    pub value: SpannedValue,
+}
+
+impl SpannedValue {
+    /// Drops the spans, returning the plain value.
+    pub fn to_value(&self) -> JsonValue {
+        match &self.kind {
+            SpannedKind::Null => JsonValue::Null,
+            SpannedKind::Bool(b) => JsonValue::Bool(*b),
+            SpannedKind::Number(n) => JsonValue::Number(*n),
+            SpannedKind::String(s) => JsonValue::String(s.clone()),
+            SpannedKind::Array(values) => {
+                JsonValue::Array(values.iter().map(SpannedValue::to_value).collect())
+            }
+            SpannedKind::Object(entries) => JsonValue::Object(
+                entries
+                    .iter()
+                    .map(|entry| (entry.key.clone(), entry.value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parses `content`, recording the source byte span of every value.
+pub fn parse_spanned(content: &str) -> Result<SpannedValue, Error> {
+    let (tokens, _) = tokenize_located(content, &TokenizeOptions::default())?;
+    parse_all(content, &tokens)
+}
+
+/// Parses `tokens`, read from `content`, as a single value, reporting an error
+/// with the position of the token it was found at.
+pub(crate) fn parse_all(content: &str, tokens: &[SpannedToken]) -> Result<SpannedValue, Error> {
+    let mut position = 0;
+    let result = parse_value(tokens, &mut position).and_then(|value| match tokens.get(position) {
+        Some(spanned) => Err(JsonParserError::UnexpectedToken(spanned.token.clone())),
+        None => Ok(value),
+    });
+    result.map_err(|error| locate(content, tokens, position, error))
+}
+
+/// Attaches to `error` the position of the token at `position`, or of the end of
+/// `content` if the tokens ran out.
+pub(crate) fn locate(
+    content: &str,
+    tokens: &[SpannedToken],
+    position: usize,
+    error: JsonParserError,
+) -> Error {
+    let offset = tokens
+        .get(position)
+        .map_or(content.len(), |spanned| spanned.span.start);
+    Error::Parse(error, Some(Position::at(content, offset)))
+}
+
+pub(crate) fn parse_value(
+    tokens: &[SpannedToken],
+    position: &mut usize,
+) -> Result<SpannedValue, JsonParserError> {
+    parse_nested_value(tokens, position, 0)
+}
+
+/// Parses the value at `position`, nested in `depth` arrays and objects.
+fn parse_nested_value(
+    tokens: &[SpannedToken],
+    position: &mut usize,
+    depth: usize,
+) -> Result<SpannedValue, JsonParserError> {
+    let spanned = match tokens.get(*position) {
+        Some(spanned) => spanned,
+        None => return Err(JsonParserError::UnexpectedEndOfInput),
+    };
+
+    let kind = match &spanned.token {
+        JsonToken::Null => SpannedKind::Null,
+        JsonToken::True => SpannedKind::Bool(true),
+        JsonToken::False => SpannedKind::Bool(false),
+        JsonToken::Number(number) => SpannedKind::Number(*number),
+        JsonToken::String(string) => SpannedKind::String(string.clone()),
+        JsonToken::LeftSquareBracket | JsonToken::LeftCurlyBracket
+            if depth == MAX_NESTING_DEPTH =>
+        {
+            return Err(JsonParserError::TooDeep)
+        }
+        JsonToken::LeftSquareBracket => return parse_array(tokens, position, depth + 1),
+        JsonToken::LeftCurlyBracket => return parse_object(tokens, position, depth + 1),
+        token => return Err(JsonParserError::ExpectedValue(token.clone())),
+    };
+    *position += 1;
+
+    Ok(SpannedValue {
+        kind,
+        span: spanned.span,
+    })
+}
+
+fn parse_array(
+    tokens: &[SpannedToken],
+    position: &mut usize,
+    depth: usize,
+) -> Result<SpannedValue, JsonParserError> {
+    let start = tokens[*position].span.start;
+    let mut values = Vec::new();
+
+    *position += 1; // consume the LeftSquareBracket
+
+    if let Some(spanned) = tokens.get(*position) {
+        if spanned.token == JsonToken::RightSquareBracket {
+            *position += 1;
+            return Ok(SpannedValue {
+                kind: SpannedKind::Array(values),
+                span: Span {
+                    start,
+                    end: spanned.span.end,
+                },
+            });
+        }
+        values.push(parse_nested_value(tokens, position, depth)?);
+    }
+
+    while let Some(spanned) = tokens.get(*position) {
+        match spanned.token {
+            JsonToken::Comma => {
+                *position += 1;
+                values.push(parse_nested_value(tokens, position, depth)?);
+            }
+            JsonToken::RightSquareBracket => {
+                *position += 1;
+                return Ok(SpannedValue {
+                    kind: SpannedKind::Array(values),
+                    span: Span {
+                        start,
+                        end: spanned.span.end,
+                    },
+                });
+            }
+            _ => return Err(JsonParserError::UnexpectedToken(spanned.token.clone())),
+        }
+    }
+
+    Err(JsonParserError::UnexpectedEndOfInput)
+}
+
+fn parse_object(
+    tokens: &[SpannedToken],
+    position: &mut usize,
+    depth: usize,
+) -> Result<SpannedValue, JsonParserError> {
+    let start = tokens[*position].span.start;
+    let mut entries = Vec::new();
+
+    *position += 1; // consume the LeftCurlyBracket
+
+    if let Some(spanned) = tokens.get(*position) {
+        if spanned.token == JsonToken::RightCurlyBracket {
+            *position += 1;
+            return Ok(SpannedValue {
+                kind: SpannedKind::Object(entries),
+                span: Span {
+                    start,
+                    end: spanned.span.end,
+                },
+            });
+        }
+        entries.push(parse_entry(tokens, position, depth)?);
+    }
+
+    while let Some(spanned) = tokens.get(*position) {
+        match spanned.token {
+            JsonToken::Comma => {
+                *position += 1;
+                entries.push(parse_entry(tokens, position, depth)?);
+            }
+            JsonToken::RightCurlyBracket => {
+                *position += 1;
+                return Ok(SpannedValue {
+                    kind: SpannedKind::Object(entries),
+                    span: Span {
+                        start,
+                        end: spanned.span.end,
+                    },
+                });
+            }
+            _ => return Err(JsonParserError::UnexpectedToken(spanned.token.clone())),
+        }
+    }
+
+    Err(JsonParserError::UnexpectedEndOfInput)
+}
+
+fn parse_entry(
+    tokens: &[SpannedToken],
+    position: &mut usize,
+    depth: usize,
+) -> Result<SpannedEntry, JsonParserError> {
+    let (key, key_span) = match tokens.get(*position) {
+        Some(SpannedToken {
+            token: JsonToken::String(key),
+            span,
+        }) => (key.clone(), *span),
+        Some(spanned) => return Err(unexpected_key(&spanned.token)),
+        None => return Err(JsonParserError::UnexpectedEndOfInput),
+    };
+    *position += 1;
+
+    match tokens.get(*position) {
+        Some(SpannedToken {
+            token: JsonToken::Colon,
+            ..
+        }) => *position += 1,
+        Some(spanned) => return Err(JsonParserError::UnexpectedToken(spanned.token.clone())),
+        None => return Err(JsonParserError::UnexpectedEndOfInput),
+    }
+
+    let value = parse_nested_value(tokens, position, depth)?;
+
+    Ok(SpannedEntry {
+        key,
+        key_span,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_spanned_top_level_object() {
+        let content = r#" {"a": {"b": "hello"}, "c": [1, 2]} "#;
+        let value = parse_spanned(content).unwrap();
+        assert_eq!(value.span, Span { start: 1, end: 35 });
+        assert_eq!(value.to_value(), crate::parse(content).unwrap());
+    }
+
+    #[test]
+    fn parse_spanned_nested_string() {
+        let content = r#"{"a": {"b": "hello"}}"#;
+        let value = parse_spanned(content).unwrap();
+
+        let SpannedKind::Object(entries) = &value.kind else {
+            panic!("Expected object");
+        };
+        let SpannedKind::Object(inner) = &entries[0].value.kind else {
+            panic!("Expected object");
+        };
+        assert_eq!(inner[0].key_span, Span { start: 7, end: 10 });
+        assert_eq!(inner[0].value.span, Span { start: 12, end: 19 });
+        assert_eq!(&content[12..19], r#""hello""#);
+    }
+
+    #[test]
+    fn parse_spanned_unexpected_end_of_input() {
+        let result = parse_spanned("[1,");
+        assert_eq!(
+            result,
+            Err(Error::Parse(
+                JsonParserError::UnexpectedEndOfInput,
+                Some(Position::at("[1,", 3))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_spanned_missing_colon() {
+        let content = r#"{"a" 1}"#;
+        let expected = || {
+            Error::Parse(
+                JsonParserError::UnexpectedToken(JsonToken::Number(JsonNumber::Integer(1))),
+                Some(Position::at(content, 5)),
+            )
+        };
+        assert_eq!(parse_spanned(content), Err(expected()));
+        assert_eq!(
+            crate::comments::parse_with_comments(content).err(),
+            Some(expected())
+        );
+        assert_eq!(crate::parse(content), Err(expected()));
+        assert_eq!(
+            parse_spanned(r#"{"a""#),
+            Err(Error::Parse(
+                JsonParserError::UnexpectedEndOfInput,
+                Some(Position::at(r#"{"a""#, 4))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_spanned_depth_limit() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let too_deep = || {
+            Error::Parse(
+                JsonParserError::TooDeep,
+                Some(Position::at(
+                    &nested(MAX_NESTING_DEPTH + 1),
+                    MAX_NESTING_DEPTH,
+                )),
+            )
+        };
+        assert!(parse_spanned(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert_eq!(
+            parse_spanned(&nested(MAX_NESTING_DEPTH + 1)),
+            Err(too_deep())
+        );
+
+        let commented = crate::comments::parse_with_comments(&nested(MAX_NESTING_DEPTH)).unwrap();
+        let formatted = crate::comments::format_with_comments(&commented, &Default::default());
+        assert_eq!(formatted.lines().count(), 2 * MAX_NESTING_DEPTH - 1);
+        assert_eq!(
+            crate::comments::parse_with_comments(&nested(MAX_NESTING_DEPTH + 1)).err(),
+            Some(too_deep())
+        );
+    }
+}
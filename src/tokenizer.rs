@@ -1,4 +1,5 @@
-use std::{iter::Peekable, str::Chars};
+use crate::error::{Error, Position};
+use crate::parser::JsonNumber;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum JsonToken {
@@ -12,7 +13,7 @@ pub enum JsonToken {
     False,
     Null,
     String(String),
-    Number(f64),
+    Number(JsonNumber),
 }
 
 impl std::fmt::Display for JsonToken {
@@ -40,71 +41,606 @@ pub enum JsonTokenizeError {
     UnexpectedEndOfInput,
     InvalidEscapeCharacter(String),
     InvalidNumberLiteral(String),
+    /// The input is longer than the given number of bytes.
+    InputTooLarge(usize),
+    /// The input has more than the given number of tokens.
+    TooManyTokens(usize),
+}
+
+impl std::fmt::Display for JsonTokenizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JsonTokenizeError::UnexpectedLiteral(literal) => {
+                write!(f, "Unexpected literal: '{}'", literal)
+            }
+            JsonTokenizeError::UnexpectedCharacter(character) => {
+                write!(f, "Unexpected character: '{}'", character)
+            }
+            JsonTokenizeError::UnexpectedEndOfInput => write!(f, "Unexpected end of input"),
+            JsonTokenizeError::InvalidEscapeCharacter(character) => {
+                write!(f, "Invalid escape character: '{}'", character)
+            }
+            JsonTokenizeError::InvalidNumberLiteral(literal) => {
+                write!(f, "Invalid number literal: '{}'", literal)
+            }
+            JsonTokenizeError::InputTooLarge(limit) => {
+                write!(f, "Input is larger than the limit of {} bytes", limit)
+            }
+            JsonTokenizeError::TooManyTokens(limit) => {
+                write!(f, "Input has more than the limit of {} tokens", limit)
+            }
+        }
+    }
 }
 
 type JsonTokenizeResult = Result<Vec<JsonToken>, JsonTokenizeError>;
 
+/// Relaxations of the JSON grammar accepted by the tokenizer.
+///
+/// The default is strict: every option is off.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenizeOptions {
+    /// Accept `_` between digits of a number, as in `1_000_000`.
+    pub allow_numeric_separators: bool,
+    /// Accept hexadecimal integers such as `0x1F` and `-0xff`.
+    pub allow_hex_numbers: bool,
+    /// Accept numbers outside the RFC 8259 grammar that still have an obvious
+    /// value, such as `01`, `1.` and `1.e5`.
+    pub allow_lax_numbers: bool,
+    /// Accept the control characters U+0000 to U+001F, such as a raw newline,
+    /// unescaped inside strings.
+    pub allow_control_characters: bool,
+    /// Skip `// line` and `/* block */` comments as if they were whitespace.
+    pub allow_comments: bool,
+    /// Accept `true`, `false` and `null` in any case, such as `TRUE` or `Null`.
+    pub case_insensitive_literals: bool,
+    /// Accept Python's `None`, `True` and `False` as `null`, `true` and `false`.
+    pub python_literals: bool,
+    /// Accept strings in single quotes, as in `'it\'s'`, where `"` needs no
+    /// escape. `\'` is then accepted in double-quoted strings too.
+    pub allow_single_quotes: bool,
+    /// Decode a `\u` escape of a lone UTF-16 surrogate, which is not a valid
+    /// character, as this character (typically U+FFFD) instead of failing.
+    pub replacement_char: Option<char>,
+    /// Fail with `TooManyTokens` as soon as the input has more tokens than this,
+    /// to bound the work done on adversarial input before parsing starts.
+    pub max_tokens: Option<usize>,
+}
+
 pub fn tokenize(input: &str) -> JsonTokenizeResult {
-    let mut chars = input.chars().peekable();
+    tokenize_with_options(input, &TokenizeOptions::default())
+}
+
+pub fn tokenize_with_options(input: &str, options: &TokenizeOptions) -> JsonTokenizeResult {
+    let tokens = tokenize_spanned(input, options)?;
+    Ok(tokens.into_iter().map(|spanned| spanned.token).collect())
+}
+
+/// A range of byte offsets into the tokenized input; `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: JsonToken,
+    pub span: Span,
+}
+
+/// Tokenizes `input`, recording the byte span each token was read from.
+pub fn tokenize_spanned(
+    input: &str,
+    options: &TokenizeOptions,
+) -> Result<Vec<SpannedToken>, JsonTokenizeError> {
+    spanned_tokens(input, options).collect()
+}
+
+/// Lazily tokenizes `input`, reading one token per call to `next`.
+///
+/// A byte order mark (U+FEFF) is skipped at the start of `input`, but is an
+/// `UnexpectedCharacter` anywhere else. Iteration stops after the first error.
+pub fn spanned_tokens<'a>(input: &'a str, options: &'a TokenizeOptions) -> SpannedTokens<'a> {
+    let mut tokens = spanned_tokens_within(input, options);
+    if tokens.chars.peek() == Some('\u{FEFF}') {
+        tokens.chars.next();
+    }
+    tokens
+}
+
+/// Lazily tokenizes `input` like `spanned_tokens`, for input that doesn't start
+/// the document, so a byte order mark at its start is not skipped.
+fn spanned_tokens_within<'a>(input: &'a str, options: &'a TokenizeOptions) -> SpannedTokens<'a> {
+    SpannedTokens {
+        chars: Cursor::new(input),
+        options,
+        comments: Vec::new(),
+        count: 0,
+        start: 0,
+        failed: false,
+    }
+}
+
+/// Tokenizes `input` like `tokenize`, calling `progress` with the number of
+/// bytes read so far each time at least another `interval` bytes have been read,
+/// and once more with the input length when tokenizing succeeds.
+///
+/// Progress is reported between tokens, so a single long string is reported
+/// once, after it has been read in full.
+pub fn tokenize_with_progress(
+    input: &str,
+    interval: usize,
+    mut progress: impl FnMut(usize),
+) -> JsonTokenizeResult {
+    let options = TokenizeOptions::default();
     let mut tokens = Vec::new();
+    let mut reported = 0;
+    for spanned in spanned_tokens(input, &options) {
+        let spanned = spanned?;
+        if spanned.span.end - reported >= interval.max(1) {
+            reported = spanned.span.end;
+            progress(reported);
+        }
+        tokens.push(spanned.token);
+    }
+    if reported < input.len() || input.is_empty() {
+        progress(input.len());
+    }
+    Ok(tokens)
+}
 
-    while let Some(&char) = chars.peek() {
-        match char {
+/// Tokenizes input that arrives in chunks, such as an editor buffer being typed,
+/// keeping what can't be tokenized yet until the next chunk.
+///
+/// A number or literal at the end of a chunk is held back, since the next chunk
+/// may continue it, as is a string, comment or literal that the chunk cuts off.
+/// The held back input is only read again once a chunk brings a character that
+/// could end it, so an error in it may be reported a few chunks late. The
+/// `max_tokens` limit counts the tokens of every chunk together.
+pub struct Tokenizer {
+    options: TokenizeOptions,
+    /// The input after the last token returned, starting at the unfinished token
+    /// or comment, if any.
+    pending: String,
+    /// The number of tokens returned so far.
+    count: usize,
+    /// Whether the pending input ends in a backslash that escapes the next
+    /// character of a string.
+    escaped: bool,
+    /// Whether the pending input ends in a `*` that can close a block comment.
+    star: bool,
+    /// Whether input has been taken from the start of the pending input, so that
+    /// it no longer starts the document.
+    started: bool,
+}
+
+impl Tokenizer {
+    pub fn new(options: TokenizeOptions) -> Self {
+        Tokenizer {
+            options,
+            pending: String::new(),
+            count: 0,
+            escaped: false,
+            star: false,
+            started: false,
+        }
+    }
+
+    /// Adds `chunk` to the input and returns the tokens it completes.
+    pub fn feed(&mut self, chunk: &str) -> JsonTokenizeResult {
+        let unchanged = !self.finishes(chunk);
+        self.pending.push_str(chunk);
+        if unchanged {
+            return Ok(Vec::new());
+        }
+        self.complete_tokens(false)
+    }
+
+    /// Ends the input and returns the remaining tokens, failing if the input
+    /// stops within one.
+    pub fn finish(mut self) -> JsonTokenizeResult {
+        self.complete_tokens(true)
+    }
+
+    /// Whether `chunk` may end the unfinished token or comment that the pending
+    /// input starts with, so that it is worth reading again. Until then, only the
+    /// new chunks are looked at, keeping track of escapes and of a `*` that may
+    /// start a comment's closing `*/`.
+    fn finishes(&mut self, chunk: &str) -> bool {
+        let mut pending = self.pending.chars();
+        match (pending.next(), pending.next()) {
+            (Some(quote @ ('"' | '\'')), _) if quote == '"' || self.options.allow_single_quotes => {
+                for char in chunk.chars() {
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if char == '\\' {
+                        self.escaped = true;
+                    } else if char == quote {
+                        return true;
+                    }
+                }
+                false
+            }
+            (Some('/'), Some('/')) => chunk.contains('\n'),
+            (Some('/'), Some('*')) => {
+                for char in chunk.chars() {
+                    if self.star && char == '/' {
+                        return true;
+                    }
+                    self.star = char == '*';
+                }
+                false
+            }
+            (Some('/'), None) | (None, _) => true,
+            _ => chunk.contains(|char: char| {
+                !(char.is_alphanumeric() || matches!(char, '.' | '+' | '-' | '_'))
+            }),
+        }
+    }
+
+    fn complete_tokens(&mut self, last: bool) -> JsonTokenizeResult {
+        let mut tokens = if self.started {
+            spanned_tokens_within(&self.pending, &self.options)
+        } else {
+            spanned_tokens(&self.pending, &self.options)
+        };
+        tokens.count = self.count;
+        let mut complete = Vec::new();
+        // Where the input that is not yet complete starts.
+        let unfinished;
+        loop {
+            let before = tokens.offset();
+            match tokens.next() {
+                Some(Ok(spanned)) => {
+                    let extensible = matches!(
+                        spanned.token,
+                        JsonToken::Number(_) | JsonToken::True | JsonToken::False | JsonToken::Null
+                    );
+                    if extensible && spanned.span.end == self.pending.len() && !last {
+                        unfinished = spanned.span.start;
+                        break;
+                    }
+                    complete.push(spanned.token);
+                }
+                // An error at the end of the input may go away once more arrives.
+                // It was found in the first token or comment not yet skipped.
+                Some(Err(error)) if at_end(&error, &tokens) && !last => {
+                    let skipped = match tokens.comments.last() {
+                        Some(comment) => comment.span.end.max(before),
+                        None => before,
+                    };
+                    let rest = &self.pending[skipped..];
+                    unfinished = self.pending.len() - rest.trim_start_matches(WHITESPACE).len();
+                    break;
+                }
+                Some(Err(error)) => return Err(error),
+                // A line comment at the end may continue in the next chunk.
+                None => {
+                    unfinished = match tokens.comments.last() {
+                        Some(comment)
+                            if comment.kind == CommentKind::Line
+                                && comment.span.end == self.pending.len() =>
+                        {
+                            comment.span.start
+                        }
+                        _ => self.pending.len(),
+                    };
+                    break;
+                }
+            }
+        }
+        self.count += complete.len();
+        self.pending.drain(..unfinished);
+        self.started |= unfinished > 0;
+        let backslashes = self.pending.len() - self.pending.trim_end_matches('\\').len();
+        self.escaped = backslashes % 2 == 1;
+        self.star = self.pending.len() > 2 && self.pending.ends_with('*');
+        Ok(complete)
+    }
+}
+
+/// The whitespace skipped between tokens.
+const WHITESPACE: [char; 4] = [' ', '\n', '\t', '\r'];
+
+/// Whether `error` was found at the end of the input, so that more input may
+/// make it go away. Exceeding `max_tokens` never does.
+fn at_end(error: &JsonTokenizeError, tokens: &SpannedTokens) -> bool {
+    !matches!(error, JsonTokenizeError::TooManyTokens(_)) && tokens.chars.rest().is_empty()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommentKind {
+    Line,
+    Block,
+}
+
+/// A comment skipped by the tokenizer when `allow_comments` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The comment text without its `//` or `/* */` markers and surrounding whitespace.
+    pub text: String,
+    pub kind: CommentKind,
+    pub span: Span,
+}
+
+/// Tokenizes `input` like `tokenize_spanned`, also returning the comments it skipped.
+pub fn tokenize_with_comments(
+    input: &str,
+    options: &TokenizeOptions,
+) -> Result<(Vec<SpannedToken>, Vec<Comment>), JsonTokenizeError> {
+    let mut tokens = spanned_tokens(input, options);
+    let spanned = tokens.by_ref().collect::<Result<Vec<_>, _>>()?;
+    Ok((spanned, tokens.comments))
+}
+
+/// Tokenizes `input` like `tokenize_with_comments`, reporting an error with the
+/// position in `input` where it was found.
+pub(crate) fn tokenize_located(
+    input: &str,
+    options: &TokenizeOptions,
+) -> Result<(Vec<SpannedToken>, Vec<Comment>), Error> {
+    let mut tokens = spanned_tokens(input, options);
+    let mut spanned = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Ok(token) => spanned.push(token),
+            Err(error) => {
+                let position = Position::at(input, tokens.error_offset());
+                return Err(Error::Tokenize(error, Some(position)));
+            }
+        }
+    }
+    Ok((spanned, tokens.comments))
+}
+
+pub struct SpannedTokens<'a> {
+    chars: Cursor<'a>,
+    options: &'a TokenizeOptions,
+    comments: Vec<Comment>,
+    /// The number of tokens read so far.
+    count: usize,
+    /// The byte offset of the token or comment read last, or being read.
+    start: usize,
+    failed: bool,
+}
+
+impl SpannedTokens<'_> {
+    /// The byte offset tokenizing has reached.
+    pub(crate) fn offset(&self) -> usize {
+        self.chars.offset()
+    }
+
+    /// The byte offset of the token or comment an error was found in, such as
+    /// the start of a malformed literal or an unterminated string.
+    pub(crate) fn error_offset(&self) -> usize {
+        self.start
+    }
+}
+
+impl Iterator for SpannedTokens<'_> {
+    type Item = Result<SpannedToken, JsonTokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let mut result = next_token(
+            &mut self.chars,
+            self.options,
+            &mut self.comments,
+            &mut self.start,
+        )
+        .transpose();
+        if let (Some(Ok(_)), Some(max_tokens)) = (&result, self.options.max_tokens) {
+            self.count += 1;
+            if self.count > max_tokens {
+                result = Some(Err(JsonTokenizeError::TooManyTokens(max_tokens)));
+            }
+        }
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+/// Reads the next token, skipping whitespace and comments, and sets `start` to
+/// the offset of each token or comment as it starts reading it.
+fn next_token(
+    chars: &mut Cursor,
+    options: &TokenizeOptions,
+    comments: &mut Vec<Comment>,
+    start: &mut usize,
+) -> Result<Option<SpannedToken>, JsonTokenizeError> {
+    while let Some(char) = chars.peek() {
+        *start = chars.offset();
+        let token = match char {
             ' ' | '\n' | '\t' | '\r' => {
                 chars.next();
+                continue;
+            }
+            '/' if options.allow_comments => {
+                comments.push(tokenize_comment(chars)?);
+                continue;
             }
             '[' => {
                 chars.next();
-                tokens.push(JsonToken::LeftSquareBracket);
+                JsonToken::LeftSquareBracket
             }
             '{' => {
                 chars.next();
-                tokens.push(JsonToken::LeftCurlyBracket);
+                JsonToken::LeftCurlyBracket
             }
             ']' => {
                 chars.next();
-                tokens.push(JsonToken::RightSquareBracket);
+                JsonToken::RightSquareBracket
             }
             '}' => {
                 chars.next();
-                tokens.push(JsonToken::RightCurlyBracket);
+                JsonToken::RightCurlyBracket
             }
             ':' => {
                 chars.next();
-                tokens.push(JsonToken::Colon);
+                JsonToken::Colon
             }
             ',' => {
                 chars.next();
-                tokens.push(JsonToken::Comma);
+                JsonToken::Comma
             }
-            '"' => match tokenize_string(&mut chars) {
-                Ok(token) => tokens.push(token),
-                Err(err) => return Err(err),
-            },
-            '-' | '0'..='9' => match tokenize_number(&mut chars) {
-                Ok(token) => tokens.push(token),
-                Err(err) => return Err(err),
-            },
-            _ => match tokenize_literal(&mut chars) {
-                Ok(token) => tokens.push(token),
-                Err(err) => return Err(err),
+            '"' => tokenize_string(chars, options)?,
+            '\'' if options.allow_single_quotes => tokenize_string(chars, options)?,
+            '-' | '+' | '0'..='9' => tokenize_number(chars, options)?,
+            _ if is_stray_character(char) => {
+                return Err(JsonTokenizeError::UnexpectedCharacter(char));
+            }
+            _ => tokenize_literal(chars, options)?,
+        };
+        return Ok(Some(SpannedToken {
+            token,
+            span: Span {
+                start: *start,
+                end: chars.offset(),
             },
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Iterates over the characters of the input while keeping track of the byte offset.
+///
+/// ASCII-only input is read byte by byte, skipping UTF-8 decoding entirely.
+#[derive(Clone)]
+struct Cursor<'a> {
+    rest: &'a str,
+    offset: usize,
+    ascii: bool,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Cursor::with_ascii_fast_path(input, input.is_ascii())
+    }
+
+    fn with_ascii_fast_path(input: &'a str, ascii: bool) -> Self {
+        Cursor {
+            rest: input,
+            offset: 0,
+            ascii,
         }
     }
 
-    Ok(tokens)
+    fn peek(&self) -> Option<char> {
+        if self.ascii {
+            self.rest.as_bytes().first().map(|&byte| byte as char)
+        } else {
+            self.rest.chars().next()
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn rest(&self) -> &str {
+        self.rest
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        let len = if self.ascii { 1 } else { char.len_utf8() };
+        self.rest = &self.rest[len..];
+        self.offset += len;
+        Some(char)
+    }
+}
+
+fn tokenize_comment(chars: &mut Cursor) -> Result<Comment, JsonTokenizeError> {
+    let start = chars.offset();
+    chars.next(); // consume the first slash
+
+    let mut text = String::new();
+    let kind = match chars.next() {
+        Some('/') => {
+            while let Some(char) = chars.peek() {
+                if char == '\n' {
+                    break;
+                }
+                text.push(char);
+                chars.next();
+            }
+            CommentKind::Line
+        }
+        Some('*') => {
+            loop {
+                match chars.next() {
+                    Some('*') if chars.peek() == Some('/') => {
+                        chars.next();
+                        break;
+                    }
+                    Some(char) => text.push(char),
+                    None => return Err(JsonTokenizeError::UnexpectedEndOfInput),
+                }
+            }
+            CommentKind::Block
+        }
+        Some(char) => return Err(JsonTokenizeError::UnexpectedCharacter(char)),
+        None => return Err(JsonTokenizeError::UnexpectedEndOfInput),
+    };
+
+    Ok(Comment {
+        text: text.trim().to_string(),
+        kind,
+        span: Span {
+            start,
+            end: chars.offset(),
+        },
+    })
+}
+
+/// Decodes a JSON string literal, quotes included, into the string it denotes.
+///
+/// This is the inverse of `escape_string`.
+pub fn unescape_string(literal: &str) -> Result<String, JsonTokenizeError> {
+    let options = TokenizeOptions::default();
+    let mut chars = Cursor::new(literal);
+    match chars.peek() {
+        Some('"') => {}
+        Some(char) => return Err(JsonTokenizeError::UnexpectedCharacter(char)),
+        None => return Err(JsonTokenizeError::UnexpectedEndOfInput),
+    }
+    let JsonToken::String(string) = tokenize_string(&mut chars, &options)? else {
+        unreachable!("tokenize_string only returns strings");
+    };
+    match chars.peek() {
+        Some(char) => Err(JsonTokenizeError::UnexpectedCharacter(char)),
+        None => Ok(string),
+    }
 }
 
-fn tokenize_string(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokenizeError> {
-    chars.next(); // consume the opening quote
+fn tokenize_string(
+    chars: &mut Cursor,
+    options: &TokenizeOptions,
+) -> Result<JsonToken, JsonTokenizeError> {
+    let quote = chars.next(); // consume the opening quote
 
     let mut string_value = String::new();
 
-    while let Some(char) = chars.next() {
+    loop {
+        let Some(char) = chars.next() else {
+            return Err(JsonTokenizeError::UnexpectedEndOfInput);
+        };
         match char {
-            '"' => break,
+            _ if Some(char) == quote => break,
+            '\u{0}'..='\u{1f}' if !options.allow_control_characters => {
+                return Err(JsonTokenizeError::UnexpectedCharacter(char))
+            }
             '\\' => match chars.next() {
                 Some('"') => string_value.push('\u{0022}'),
+                Some('\'') if options.allow_single_quotes => string_value.push('\u{0027}'),
                 Some('\\') => string_value.push('\u{005C}'),
                 Some('/') => string_value.push('\u{002F}'),
                 Some('b') => string_value.push('\u{0008}'),
@@ -112,31 +648,7 @@ fn tokenize_string(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokeniz
                 Some('n') => string_value.push('\u{000A}'),
                 Some('r') => string_value.push('\u{000D}'),
                 Some('t') => string_value.push('\u{0009}'),
-                Some('u') => {
-                    let mut hex_chars = String::new();
-                    while let Some(&char) = chars.peek() {
-                        if char == '"' {
-                            break;
-                        } else {
-                            hex_chars.push(char);
-                            chars.next();
-                            if hex_chars.len() == 4 {
-                                break;
-                            }
-                        }
-                    }
-
-                    if hex_chars.len() != 4 {
-                        return Err(JsonTokenizeError::InvalidEscapeCharacter(hex_chars));
-                    }
-
-                    if let Ok(hex_as_char) = u32::from_str_radix(&hex_chars, 16).unwrap().try_into()
-                    {
-                        string_value.push(hex_as_char);
-                    } else {
-                        return Err(JsonTokenizeError::InvalidEscapeCharacter(hex_chars));
-                    }
-                }
+                Some('u') => string_value.push(tokenize_unicode_escape(chars, options)?),
                 Some(char) => {
                     return Err(JsonTokenizeError::InvalidEscapeCharacter(char.to_string()))
                 }
@@ -149,12 +661,74 @@ fn tokenize_string(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokeniz
     Ok(JsonToken::String(string_value))
 }
 
-fn tokenize_number(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokenizeError> {
+/// Decodes the `XXXX` of a `\uXXXX` escape, combining a UTF-16 surrogate pair
+/// written as two consecutive escapes into one character.
+fn tokenize_unicode_escape(
+    chars: &mut Cursor,
+    options: &TokenizeOptions,
+) -> Result<char, JsonTokenizeError> {
+    let (code_unit, hex_chars) = tokenize_hex_digits(chars)?;
+
+    if (0xD800..0xDC00).contains(&code_unit) {
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('\\') && lookahead.next() == Some('u') {
+            if let Ok((low @ 0xDC00..=0xDFFF, _)) = tokenize_hex_digits(&mut lookahead) {
+                *chars = lookahead;
+                let code_point = 0x10000 + ((code_unit - 0xD800) << 10) + (low - 0xDC00);
+                return Ok(char::from_u32(code_point).unwrap());
+            }
+        }
+    }
+
+    match (char::from_u32(code_unit), options.replacement_char) {
+        (Some(char), _) => Ok(char),
+        // A surrogate without its other half.
+        (None, Some(replacement)) => Ok(replacement),
+        (None, None) => Err(JsonTokenizeError::InvalidEscapeCharacter(hex_chars)),
+    }
+}
+
+fn tokenize_hex_digits(chars: &mut Cursor) -> Result<(u32, String), JsonTokenizeError> {
+    let mut hex_chars = String::new();
+    while let Some(char) = chars.peek() {
+        if char == '"' {
+            break;
+        } else {
+            hex_chars.push(char);
+            chars.next();
+            if hex_chars.len() == 4 {
+                break;
+            }
+        }
+    }
+
+    if hex_chars.len() != 4 {
+        return Err(JsonTokenizeError::InvalidEscapeCharacter(hex_chars));
+    }
+
+    // `from_str_radix` alone would also accept a leading `+`.
+    match u32::from_str_radix(&hex_chars, 16) {
+        Ok(code_unit) if hex_chars.chars().all(|char| char.is_ascii_hexdigit()) => {
+            Ok((code_unit, hex_chars))
+        }
+        _ => Err(JsonTokenizeError::InvalidEscapeCharacter(hex_chars)),
+    }
+}
+
+fn tokenize_number(
+    chars: &mut Cursor,
+    options: &TokenizeOptions,
+) -> Result<JsonToken, JsonTokenizeError> {
+    let unsigned = chars.rest().strip_prefix('-').unwrap_or(chars.rest());
+    if unsigned.starts_with("0x") || unsigned.starts_with("0X") {
+        return tokenize_hex_number(chars, options);
+    }
+
     let mut number_chars = String::new();
 
-    while let Some(&char) = chars.peek() {
+    while let Some(char) = chars.peek() {
         match char {
-            '0'..='9' | '-' | '+' | 'e' | 'E' | '.' => {
+            '0'..='9' | '-' | '+' | 'e' | 'E' | '.' | '_' => {
                 number_chars.push(char);
                 chars.next();
             }
@@ -162,18 +736,156 @@ fn tokenize_number(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokeniz
         }
     }
 
-    match number_chars.parse::<f64>() {
-        Ok(number) => Ok(JsonToken::Number(number)),
-        Err(_) => Err(JsonTokenizeError::InvalidNumberLiteral(number_chars)),
+    // Rust's float parser accepts a leading `+`, which JSON doesn't.
+    if number_chars.starts_with('+') {
+        return Err(JsonTokenizeError::InvalidNumberLiteral(number_chars));
+    }
+
+    let digits = if options.allow_numeric_separators {
+        match strip_numeric_separators(&number_chars) {
+            Some(digits) => digits,
+            None => return Err(JsonTokenizeError::InvalidNumberLiteral(number_chars)),
+        }
+    } else {
+        number_chars.clone()
+    };
+
+    if digits.contains('_') || !(options.allow_lax_numbers || is_number_literal(&digits)) {
+        return Err(JsonTokenizeError::InvalidNumberLiteral(number_chars));
     }
+
+    // A number too large for `f64`, such as `1e999`, would parse as infinity.
+    let float = match digits.parse::<f64>() {
+        Ok(float) if float.is_finite() => float,
+        _ => return Err(JsonTokenizeError::InvalidNumberLiteral(number_chars)),
+    };
+    // `-0` is kept as a float, since an integer zero has no sign.
+    if digits.contains(['.', 'e', 'E']) || (float == 0.0 && float.is_sign_negative()) {
+        return Ok(JsonToken::Number(JsonNumber::Float(float)));
+    }
+    match digits.parse::<i64>() {
+        Ok(integer) => Ok(JsonToken::Number(JsonNumber::Integer(integer))),
+        Err(_) => Ok(JsonToken::Number(JsonNumber::Float(float))),
+    }
+}
+
+fn tokenize_hex_number(
+    chars: &mut Cursor,
+    options: &TokenizeOptions,
+) -> Result<JsonToken, JsonTokenizeError> {
+    let mut number_chars = String::new();
+
+    if chars.peek() == Some('-') {
+        number_chars.push('-');
+        chars.next();
+    }
+    number_chars.extend(chars.by_ref().take(2)); // the 0x prefix
+    while let Some(char) = chars.peek() {
+        if !char.is_ascii_alphanumeric() {
+            break;
+        }
+        number_chars.push(char);
+        chars.next();
+    }
+
+    let negative = number_chars.starts_with('-');
+    let digits = &number_chars[if negative { 3 } else { 2 }..];
+    if !options.allow_hex_numbers
+        || digits.is_empty()
+        || !digits.chars().all(|char| char.is_ascii_hexdigit())
+    {
+        return Err(JsonTokenizeError::InvalidNumberLiteral(number_chars));
+    }
+
+    if let Ok(magnitude) = i64::from_str_radix(digits, 16) {
+        return Ok(JsonToken::Number(JsonNumber::Integer(if negative {
+            -magnitude
+        } else {
+            magnitude
+        })));
+    }
+    let magnitude = digits.chars().fold(0.0, |number: f64, char| {
+        number * 16.0 + char.to_digit(16).unwrap() as f64
+    });
+    if !magnitude.is_finite() {
+        return Err(JsonTokenizeError::InvalidNumberLiteral(number_chars));
+    }
+    Ok(JsonToken::Number(JsonNumber::Float(if negative {
+        -magnitude
+    } else {
+        magnitude
+    })))
+}
+
+/// Whether `string` matches the RFC 8259 number grammar exactly.
+pub(crate) fn is_number_literal(string: &str) -> bool {
+    let digits =
+        |rest: &str| rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    let mut rest = string.strip_prefix('-').unwrap_or(string);
+    match digits(rest) {
+        0 => return false,
+        1 => {}
+        _ if rest.starts_with('0') => return false,
+        _ => {}
+    }
+    rest = &rest[digits(rest)..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        if digits(fraction) == 0 {
+            return false;
+        }
+        rest = &fraction[digits(fraction)..];
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        if digits(exponent) == 0 {
+            return false;
+        }
+        rest = &exponent[digits(exponent)..];
+    }
+    rest.is_empty()
+}
+
+/// Removes `_` separators, which are only allowed between two digits.
+fn strip_numeric_separators(number_chars: &str) -> Option<String> {
+    let chars: Vec<char> = number_chars.chars().collect();
+    let mut digits = String::with_capacity(number_chars.len());
+
+    for (index, &char) in chars.iter().enumerate() {
+        if char == '_' {
+            let previous = index.checked_sub(1).map(|index| chars[index]);
+            let next = chars.get(index + 1);
+            if !previous.is_some_and(|char| char.is_ascii_digit())
+                || !next.is_some_and(|char| char.is_ascii_digit())
+            {
+                return None;
+            }
+        } else {
+            digits.push(char);
+        }
+    }
+
+    Some(digits)
+}
+
+/// Control characters (such as a raw NUL or form feed) and byte order marks past
+/// the start of the input can't start a token, so they are reported as such
+/// instead of as part of a literal.
+fn is_stray_character(char: char) -> bool {
+    char.is_control() || char == '\u{FEFF}'
 }
 
-fn tokenize_literal(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokenizeError> {
+fn tokenize_literal(
+    chars: &mut Cursor,
+    options: &TokenizeOptions,
+) -> Result<JsonToken, JsonTokenizeError> {
     let mut literal = String::new();
 
-    while let Some(&char) = chars.peek() {
+    while let Some(char) = chars.peek() {
         match char {
             '[' | ']' | '{' | '}' | ':' | ',' | ' ' | '\n' | '\t' | '\r' => break,
+            '/' if !literal.is_empty() => break, // a comment may follow
+            _ if is_stray_character(char) => break,
             _ => {
                 chars.next();
                 literal.push(char);
@@ -181,6 +893,24 @@ fn tokenize_literal(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokeni
         }
     }
 
+    if options.python_literals {
+        match literal.as_str() {
+            "True" => return Ok(JsonToken::True),
+            "False" => return Ok(JsonToken::False),
+            "None" => return Ok(JsonToken::Null),
+            _ => {}
+        }
+    }
+
+    if options.case_insensitive_literals {
+        match literal.to_ascii_lowercase().as_str() {
+            "true" => return Ok(JsonToken::True),
+            "false" => return Ok(JsonToken::False),
+            "null" => return Ok(JsonToken::Null),
+            _ => {}
+        }
+    }
+
     match literal.as_str() {
         "true" => Ok(JsonToken::True),
         "false" => Ok(JsonToken::False),
@@ -190,6 +920,7 @@ fn tokenize_literal(chars: &mut Peekable<Chars>) -> Result<JsonToken, JsonTokeni
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use super::*;
 
@@ -201,6 +932,59 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn tokenize_stray_control_characters() {
+        assert_eq!(
+            tokenize("[1,\u{0000}2]"),
+            Err(JsonTokenizeError::UnexpectedCharacter('\u{0000}'))
+        );
+        assert_eq!(
+            tokenize("{\"a\":\u{000C}true}"),
+            Err(JsonTokenizeError::UnexpectedCharacter('\u{000C}'))
+        );
+        assert_eq!(
+            tokenize("[true\u{0000}]"),
+            Err(JsonTokenizeError::UnexpectedCharacter('\u{0000}'))
+        );
+        assert_eq!(
+            tokenize("[1,\u{FEFF}2]"),
+            Err(JsonTokenizeError::UnexpectedCharacter('\u{FEFF}'))
+        );
+    }
+
+    #[test]
+    fn tokenize_leading_byte_order_mark() {
+        assert_eq!(
+            tokenize_spanned("\u{FEFF}[1]", &TokenizeOptions::default()).unwrap()[0].span,
+            Span { start: 3, end: 4 }
+        );
+        assert_eq!(tokenize("\u{FEFF}"), Ok(vec![]));
+
+        // Only a single byte order mark at the very start is skipped.
+        assert_eq!(
+            tokenize("\u{FEFF}\u{FEFF}[1]"),
+            Err(JsonTokenizeError::UnexpectedCharacter('\u{FEFF}'))
+        );
+        assert_eq!(
+            tokenize(" \u{FEFF}[1]"),
+            Err(JsonTokenizeError::UnexpectedCharacter('\u{FEFF}'))
+        );
+
+        let mut tokenizer = Tokenizer::new(TokenizeOptions::default());
+        assert_eq!(
+            tokenizer.feed("\u{FEFF}[1,"),
+            Ok(vec![
+                JsonToken::LeftSquareBracket,
+                JsonToken::Number(JsonNumber::Integer(1)),
+                JsonToken::Comma,
+            ])
+        );
+        assert_eq!(
+            tokenizer.feed("\u{FEFF}2]"),
+            Err(JsonTokenizeError::UnexpectedCharacter('\u{FEFF}'))
+        );
+    }
+
     #[test]
     fn tokenize_left_square_bracket() {
         let input = "[";
@@ -315,6 +1099,83 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn tokenize_unterminated_string() {
+        assert_eq!(
+            tokenize("[\"abc"),
+            Err(JsonTokenizeError::UnexpectedEndOfInput)
+        );
+    }
+
+    #[test]
+    fn unescape_string_rejects_non_literals() {
+        assert_eq!(
+            unescape_string("abc"),
+            Err(JsonTokenizeError::UnexpectedCharacter('a'))
+        );
+        assert_eq!(
+            unescape_string("\"a\" "),
+            Err(JsonTokenizeError::UnexpectedCharacter(' '))
+        );
+        assert_eq!(
+            unescape_string("\"a"),
+            Err(JsonTokenizeError::UnexpectedEndOfInput)
+        );
+    }
+
+    #[test]
+    fn tokenize_non_hex_unicode_escape() {
+        assert_eq!(
+            tokenize(r#""\uZZZZ""#),
+            Err(JsonTokenizeError::InvalidEscapeCharacter(
+                "ZZZZ".to_string()
+            ))
+        );
+        assert_eq!(
+            tokenize(r#""\u12XY""#),
+            Err(JsonTokenizeError::InvalidEscapeCharacter(
+                "12XY".to_string()
+            ))
+        );
+        assert_eq!(
+            tokenize(r#""\u+123""#),
+            Err(JsonTokenizeError::InvalidEscapeCharacter(
+                "+123".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn tokenize_surrogate_pair() {
+        assert_eq!(
+            tokenize(r#""\uD83D\uDE00""#),
+            Ok(vec![JsonToken::String("😀".to_string())])
+        );
+    }
+
+    #[test]
+    fn tokenize_lone_surrogate() {
+        for input in [r#""\uD83D""#, r#""\uD83Dx""#, r#""\uDE00""#] {
+            assert!(matches!(
+                tokenize(input),
+                Err(JsonTokenizeError::InvalidEscapeCharacter(_))
+            ));
+        }
+
+        let options = TokenizeOptions {
+            replacement_char: Some('\u{FFFD}'),
+            ..TokenizeOptions::default()
+        };
+        assert_eq!(
+            tokenize_with_options(r#""a\uD83Db\uDE00""#, &options),
+            Ok(vec![JsonToken::String("a\u{FFFD}b\u{FFFD}".to_string())])
+        );
+        assert_eq!(
+            tokenize_with_options(r#""\uD83D\u0041""#, &options),
+            Ok(vec![JsonToken::String("\u{FFFD}A".to_string())])
+        );
+    }
+
     #[test]
     fn tokenize_invalid_escape_character() {
         let input = "\"\\x\"";
@@ -331,31 +1192,286 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn tokenize_python_literals_and_single_quotes() {
+        let options = TokenizeOptions {
+            python_literals: true,
+            allow_single_quotes: true,
+            ..TokenizeOptions::default()
+        };
+        assert_eq!(
+            tokenize_with_options(
+                r#"['it\'s', "a\'b", 'say "hi"', None, True, False]"#,
+                &options
+            ),
+            Ok(vec![
+                JsonToken::LeftSquareBracket,
+                JsonToken::String("it's".to_string()),
+                JsonToken::Comma,
+                JsonToken::String("a'b".to_string()),
+                JsonToken::Comma,
+                JsonToken::String("say \"hi\"".to_string()),
+                JsonToken::Comma,
+                JsonToken::Null,
+                JsonToken::Comma,
+                JsonToken::True,
+                JsonToken::Comma,
+                JsonToken::False,
+                JsonToken::RightSquareBracket,
+            ])
+        );
+        assert_eq!(
+            tokenize_with_options("NONE", &options),
+            Err(JsonTokenizeError::UnexpectedLiteral("NONE".to_string()))
+        );
+        assert_eq!(
+            tokenize("'a'"),
+            Err(JsonTokenizeError::UnexpectedLiteral("'a'".to_string()))
+        );
+        assert_eq!(
+            tokenize("None"),
+            Err(JsonTokenizeError::UnexpectedLiteral("None".to_string()))
+        );
+    }
+
+    #[test]
+    fn tokenize_case_insensitive_literals() {
+        let options = TokenizeOptions {
+            case_insensitive_literals: true,
+            ..TokenizeOptions::default()
+        };
+        assert_eq!(
+            tokenize_with_options("[TRUE, Null, FALSE, nULL]", &options),
+            Ok(vec![
+                JsonToken::LeftSquareBracket,
+                JsonToken::True,
+                JsonToken::Comma,
+                JsonToken::Null,
+                JsonToken::Comma,
+                JsonToken::False,
+                JsonToken::Comma,
+                JsonToken::Null,
+                JsonToken::RightSquareBracket,
+            ])
+        );
+        assert_eq!(
+            tokenize_with_options("TRUEISH", &options),
+            Err(JsonTokenizeError::UnexpectedLiteral("TRUEISH".to_string()))
+        );
+
+        for literal in ["TRUE", "Null", "FALSE"] {
+            assert_eq!(
+                tokenize(literal),
+                Err(JsonTokenizeError::UnexpectedLiteral(literal.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn tokenize_with_progress_reports_positions() {
+        let input = r#"[1, "a long string value", {"key": null}]  "#;
+        let mut positions = Vec::new();
+        let tokens = tokenize_with_progress(input, 10, |position| positions.push(position));
+        assert_eq!(tokens, tokenize(input));
+        assert_eq!(positions, vec![25, 39, 43]);
+        assert_eq!(positions.last(), Some(&input.len()));
+
+        let mut positions = Vec::new();
+        assert!(tokenize_with_progress("[1, @]", 1, |position| positions.push(position)).is_err());
+        assert_eq!(positions, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn tokenize_max_tokens() {
+        let options = TokenizeOptions {
+            max_tokens: Some(7),
+            ..TokenizeOptions::default()
+        };
+        assert_eq!(
+            tokenize_with_options("[1, 2, 3]", &options).map(|tokens| tokens.len()),
+            Ok(7)
+        );
+        assert_eq!(
+            tokenize_with_options("[1, 2, 3, 4]", &options),
+            Err(JsonTokenizeError::TooManyTokens(7))
+        );
+        assert_eq!(
+            JsonTokenizeError::TooManyTokens(7).to_string(),
+            "Input has more than the limit of 7 tokens"
+        );
+    }
+
+    #[test]
+    fn tokenizer_fed_in_chunks() {
+        let input = r#"{"greeting": "hel\"lo", "n": -12.5e3, "ok": true} // done"#;
+        let options = TokenizeOptions {
+            allow_comments: true,
+            ..TokenizeOptions::default()
+        };
+        for split in 0..=input.len() {
+            let mut tokenizer = Tokenizer::new(options.clone());
+            let mut tokens = tokenizer.feed(&input[..split]).unwrap();
+            tokens.extend(tokenizer.feed(&input[split..]).unwrap());
+            tokens.extend(tokenizer.finish().unwrap());
+            assert_eq!(
+                Ok(tokens),
+                tokenize_with_options(input, &options),
+                "split at {}",
+                split
+            );
+        }
+    }
+
+    #[test]
+    fn tokenizer_fed_one_character_at_a_time() {
+        let input = format!(
+            r#"{{"text": "{}", "n": 12345678901234, /* {} * / */ "list": [true, null]}} // end"#,
+            "ab\\\"".repeat(50_000),
+            " ".repeat(50_000)
+        );
+        let options = TokenizeOptions {
+            allow_comments: true,
+            ..TokenizeOptions::default()
+        };
+        let mut tokenizer = Tokenizer::new(options.clone());
+        let mut tokens = Vec::new();
+        for (index, char) in input.char_indices() {
+            tokens.extend(
+                tokenizer
+                    .feed(&input[index..index + char.len_utf8()])
+                    .unwrap(),
+            );
+        }
+        tokens.extend(tokenizer.finish().unwrap());
+        assert_eq!(Ok(tokens), tokenize_with_options(&input, &options));
+    }
+
+    #[test]
+    fn tokenizer_max_tokens_counts_every_chunk() {
+        let options = TokenizeOptions {
+            max_tokens: Some(3),
+            ..TokenizeOptions::default()
+        };
+        let mut tokenizer = Tokenizer::new(options);
+        assert_eq!(tokenizer.feed("[1"), Ok(vec![JsonToken::LeftSquareBracket]));
+        assert_eq!(
+            tokenizer.feed(", "),
+            Ok(vec![
+                JsonToken::Number(JsonNumber::Integer(1)),
+                JsonToken::Comma
+            ])
+        );
+        assert_eq!(
+            tokenizer.feed("2]"),
+            Err(JsonTokenizeError::TooManyTokens(3))
+        );
+    }
+
+    #[test]
+    fn tokenizer_holds_back_incomplete_tokens() {
+        let mut tokenizer = Tokenizer::new(TokenizeOptions::default());
+        assert_eq!(
+            tokenizer.feed(r#"["ab"#),
+            Ok(vec![JsonToken::LeftSquareBracket])
+        );
+        assert_eq!(
+            tokenizer.feed(r#"c", 1"#),
+            Ok(vec![JsonToken::String("abc".to_string()), JsonToken::Comma])
+        );
+        assert_eq!(
+            tokenizer.feed("0, tr"),
+            Ok(vec![
+                JsonToken::Number(JsonNumber::Integer(10)),
+                JsonToken::Comma
+            ])
+        );
+        assert_eq!(
+            tokenizer.finish(),
+            Err(JsonTokenizeError::UnexpectedLiteral("tr".to_string()))
+        );
+
+        let mut tokenizer = Tokenizer::new(TokenizeOptions::default());
+        assert_eq!(
+            tokenizer.feed("[1, @]"),
+            Err(JsonTokenizeError::UnexpectedLiteral("@".to_string()))
+        );
+    }
+
+    #[test]
+    fn tokenize_number_leading_plus() {
+        assert_eq!(
+            tokenize("+123"),
+            Err(JsonTokenizeError::InvalidNumberLiteral("+123".to_string()))
+        );
+        assert_eq!(
+            tokenize("[+1.5e3]"),
+            Err(JsonTokenizeError::InvalidNumberLiteral(
+                "+1.5e3".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn tokenize_number_positive() {
-        assert_eq!(tokenize("123"), Ok(vec![JsonToken::Number(123.0)]));
-        assert_eq!(tokenize("123.456"), Ok(vec![JsonToken::Number(123.456)]));
-        assert_eq!(tokenize("123e4"), Ok(vec![JsonToken::Number(123e4)]));
-        assert_eq!(tokenize("123e+4"), Ok(vec![JsonToken::Number(123e4)]));
-        assert_eq!(tokenize("123E4"), Ok(vec![JsonToken::Number(123e4)]));
-        assert_eq!(tokenize("123e-4"), Ok(vec![JsonToken::Number(123e-4)]));
+        assert_eq!(
+            tokenize("123"),
+            Ok(vec![JsonToken::Number(JsonNumber::Integer(123))])
+        );
+        assert_eq!(
+            tokenize("123.456"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(123.456))])
+        );
+        assert_eq!(
+            tokenize("123e4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(123e4))])
+        );
+        assert_eq!(
+            tokenize("123e+4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(123e4))])
+        );
+        assert_eq!(
+            tokenize("123E4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(123e4))])
+        );
+        assert_eq!(
+            tokenize("123e-4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(123e-4))])
+        );
         assert_eq!(
             tokenize("123.456e-789"),
-            Ok(vec![JsonToken::Number(123.456e-789)])
+            Ok(vec![JsonToken::Number(JsonNumber::Float(123.456e-789))])
         );
     }
 
     #[test]
     fn tokenize_number_negative() {
-        assert_eq!(tokenize("-123"), Ok(vec![JsonToken::Number(-123.0)]));
-        assert_eq!(tokenize("-123.456"), Ok(vec![JsonToken::Number(-123.456)]));
-        assert_eq!(tokenize("-123e4"), Ok(vec![JsonToken::Number(-123e4)]));
-        assert_eq!(tokenize("-123e+4"), Ok(vec![JsonToken::Number(-123e4)]));
-        assert_eq!(tokenize("-123E4"), Ok(vec![JsonToken::Number(-123e4)]));
-        assert_eq!(tokenize("-123e-4"), Ok(vec![JsonToken::Number(-123e-4)]));
+        assert_eq!(
+            tokenize("-123"),
+            Ok(vec![JsonToken::Number(JsonNumber::Integer(-123))])
+        );
+        assert_eq!(
+            tokenize("-123.456"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(-123.456))])
+        );
+        assert_eq!(
+            tokenize("-123e4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(-123e4))])
+        );
+        assert_eq!(
+            tokenize("-123e+4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(-123e4))])
+        );
+        assert_eq!(
+            tokenize("-123E4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(-123e4))])
+        );
+        assert_eq!(
+            tokenize("-123e-4"),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(-123e-4))])
+        );
         assert_eq!(
             tokenize("-123.456e-789"),
-            Ok(vec![JsonToken::Number(-123.456e-789)])
+            Ok(vec![JsonToken::Number(JsonNumber::Float(-123.456e-789))])
         );
     }
 
@@ -368,4 +1484,246 @@ mod tests {
         ));
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn tokenize_lax_numbers() {
+        let options = TokenizeOptions {
+            allow_lax_numbers: true,
+            ..TokenizeOptions::default()
+        };
+        for (input, expected) in [
+            ("01", JsonNumber::Integer(1)),
+            ("-01.5", JsonNumber::Float(-1.5)),
+            ("1.", JsonNumber::Float(1.0)),
+            ("1.e5", JsonNumber::Float(100000.0)),
+        ] {
+            assert_eq!(
+                tokenize(input),
+                Err(JsonTokenizeError::InvalidNumberLiteral(input.to_string()))
+            );
+            assert_eq!(
+                tokenize_with_options(input, &options),
+                Ok(vec![JsonToken::Number(expected)])
+            );
+        }
+    }
+
+    #[test]
+    fn tokenize_control_characters_in_strings() {
+        let options = TokenizeOptions {
+            allow_control_characters: true,
+            ..TokenizeOptions::default()
+        };
+        for char in ['\u{0}', '\t', '\n', '\u{1f}'] {
+            let input = format!("\"a{}b\"", char);
+            assert_eq!(
+                tokenize(&input),
+                Err(JsonTokenizeError::UnexpectedCharacter(char))
+            );
+            assert_eq!(
+                tokenize_with_options(&input, &options),
+                Ok(vec![JsonToken::String(format!("a{}b", char))])
+            );
+        }
+        assert_eq!(
+            tokenize("\"\u{7f}\""),
+            Ok(vec![JsonToken::String("\u{7f}".to_string())])
+        );
+    }
+
+    #[test]
+    fn tokenize_number_with_separators_lenient() {
+        let options = TokenizeOptions {
+            allow_numeric_separators: true,
+            ..TokenizeOptions::default()
+        };
+        assert_eq!(
+            tokenize_with_options("1_000", &options),
+            Ok(vec![JsonToken::Number(JsonNumber::Integer(1000))])
+        );
+        assert_eq!(
+            tokenize_with_options("-1_000.000_1", &options),
+            Ok(vec![JsonToken::Number(JsonNumber::Float(-1000.0001))])
+        );
+        assert_eq!(
+            tokenize_with_options("1__0", &options),
+            Err(JsonTokenizeError::InvalidNumberLiteral("1__0".to_string()))
+        );
+        assert_eq!(
+            tokenize_with_options("1_", &options),
+            Err(JsonTokenizeError::InvalidNumberLiteral("1_".to_string()))
+        );
+        assert_eq!(
+            tokenize_with_options("_1", &options),
+            Err(JsonTokenizeError::UnexpectedLiteral("_1".to_string()))
+        );
+    }
+
+    #[test]
+    fn tokenize_number_with_separators_strict() {
+        assert_eq!(
+            tokenize("1_000"),
+            Err(JsonTokenizeError::InvalidNumberLiteral("1_000".to_string()))
+        );
+    }
+
+    #[test]
+    fn tokenize_spanned_records_byte_offsets() {
+        let actual = tokenize_spanned("{\"é\": 12}", &TokenizeOptions::default());
+        let expected = Ok(vec![
+            SpannedToken {
+                token: JsonToken::LeftCurlyBracket,
+                span: Span { start: 0, end: 1 },
+            },
+            SpannedToken {
+                token: JsonToken::String("é".to_string()),
+                span: Span { start: 1, end: 5 },
+            },
+            SpannedToken {
+                token: JsonToken::Colon,
+                span: Span { start: 5, end: 6 },
+            },
+            SpannedToken {
+                token: JsonToken::Number(JsonNumber::Integer(12)),
+                span: Span { start: 7, end: 9 },
+            },
+            SpannedToken {
+                token: JsonToken::RightCurlyBracket,
+                span: Span { start: 9, end: 10 },
+            },
+        ]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn tokenize_hex_number_lenient() {
+        let options = TokenizeOptions {
+            allow_hex_numbers: true,
+            ..TokenizeOptions::default()
+        };
+        assert_eq!(
+            tokenize_with_options("0x1F", &options),
+            Ok(vec![JsonToken::Number(JsonNumber::Integer(31))])
+        );
+        assert_eq!(
+            tokenize_with_options("0xff", &options),
+            Ok(vec![JsonToken::Number(JsonNumber::Integer(255))])
+        );
+        assert_eq!(
+            tokenize_with_options("[-0X1F]", &options),
+            Ok(vec![
+                JsonToken::LeftSquareBracket,
+                JsonToken::Number(JsonNumber::Integer(-31)),
+                JsonToken::RightSquareBracket
+            ])
+        );
+        assert_eq!(
+            tokenize_with_options("0x", &options),
+            Err(JsonTokenizeError::InvalidNumberLiteral("0x".to_string()))
+        );
+        assert_eq!(
+            tokenize_with_options("0xfg", &options),
+            Err(JsonTokenizeError::InvalidNumberLiteral("0xfg".to_string()))
+        );
+    }
+
+    #[test]
+    fn tokenize_hex_number_strict() {
+        assert_eq!(
+            tokenize("0x1F"),
+            Err(JsonTokenizeError::InvalidNumberLiteral("0x1F".to_string()))
+        );
+    }
+
+    #[test]
+    fn tokenize_ascii_fast_path_matches_unicode_path() {
+        let options = TokenizeOptions::default();
+        let inputs = [
+            r#"{"a": [1, -2.5e3, true, false, null], "b": {"c": "d\n\u0041"}}"#,
+            "[\"unterminated",
+            "[1, 2, nul]",
+            "\"\\x\"",
+        ];
+        for input in inputs {
+            let fast: Result<Vec<SpannedToken>, JsonTokenizeError> = SpannedTokens {
+                chars: Cursor::with_ascii_fast_path(input, true),
+                options: &options,
+                comments: Vec::new(),
+                count: 0,
+                start: 0,
+                failed: false,
+            }
+            .collect();
+            let slow: Result<Vec<SpannedToken>, JsonTokenizeError> = SpannedTokens {
+                chars: Cursor::with_ascii_fast_path(input, false),
+                options: &options,
+                comments: Vec::new(),
+                count: 0,
+                start: 0,
+                failed: false,
+            }
+            .collect();
+            assert_eq!(fast, slow);
+        }
+    }
+
+    #[test]
+    fn tokenize_skips_comments() {
+        let options = TokenizeOptions {
+            allow_comments: true,
+            ..TokenizeOptions::default()
+        };
+        let input = "// line\n[1, /* block */ true//end";
+        let (tokens, comments) = tokenize_with_comments(input, &options).unwrap();
+        let tokens: Vec<JsonToken> = tokens.into_iter().map(|spanned| spanned.token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                JsonToken::LeftSquareBracket,
+                JsonToken::Number(JsonNumber::Integer(1)),
+                JsonToken::Comma,
+                JsonToken::True
+            ]
+        );
+        assert_eq!(
+            comments,
+            vec![
+                Comment {
+                    text: "line".to_string(),
+                    kind: CommentKind::Line,
+                    span: Span { start: 0, end: 7 },
+                },
+                Comment {
+                    text: "block".to_string(),
+                    kind: CommentKind::Block,
+                    span: Span { start: 12, end: 23 },
+                },
+                Comment {
+                    text: "end".to_string(),
+                    kind: CommentKind::Line,
+                    span: Span { start: 28, end: 33 },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_unterminated_block_comment() {
+        let options = TokenizeOptions {
+            allow_comments: true,
+            ..TokenizeOptions::default()
+        };
+        assert_eq!(
+            tokenize_with_options("1 /* open", &options),
+            Err(JsonTokenizeError::UnexpectedEndOfInput)
+        );
+    }
+
+    #[test]
+    fn tokenize_comments_disallowed() {
+        assert_eq!(
+            tokenize("// line"),
+            Err(JsonTokenizeError::UnexpectedLiteral("/".to_string()))
+        );
+    }
 }
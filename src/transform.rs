@@ -0,0 +1,671 @@
+use crate::parser::{JsonNumber, JsonValue};
+use crate::tokenizer::{self, JsonToken};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyCase {
+    SnakeCase,
+    CamelCase,
+    KebabCase,
+}
+
+/// Renames every object key in `value`, at any depth, to the given naming convention.
+///
+/// Words are split on `_`, `-`, whitespace and lower-to-upper case changes, so
+/// `userID`, `user_id` and `User-Id` all become `user_id` in snake case.
+///
+/// If two keys of the same object map to the same name, the entry that appears
+/// last wins and takes the position of the first one.
+pub fn rename_keys(value: &mut JsonValue, style: KeyCase) {
+    match value {
+        JsonValue::Object(entries) => {
+            let mut renamed: Vec<(String, JsonValue)> = Vec::with_capacity(entries.len());
+
+            for (key, mut value) in entries.drain(..) {
+                rename_keys(&mut value, style);
+                let key = convert_key(&key, style);
+                match renamed.iter_mut().find(|(existing, _)| *existing == key) {
+                    Some(entry) => entry.1 = value,
+                    None => renamed.push((key, value)),
+                }
+            }
+
+            *entries = renamed;
+        }
+        JsonValue::Array(values) => {
+            for value in values {
+                rename_keys(value, style);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn convert_key(key: &str, style: KeyCase) -> String {
+    let words = split_words(key);
+
+    match style {
+        KeyCase::SnakeCase => words.join("_"),
+        KeyCase::KebabCase => words.join("-"),
+        KeyCase::CamelCase => words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| {
+                if index == 0 {
+                    return word.clone();
+                }
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect(),
+    }
+}
+
+fn split_words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (index, &char) in chars.iter().enumerate() {
+        if char == '_' || char == '-' || char.is_whitespace() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if char.is_uppercase() && !current.is_empty() {
+            let previous = chars[index - 1];
+            let next_is_lowercase = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next_is_lowercase)
+            {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+
+        current.extend(char.to_lowercase());
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+/// Replaces every string value in `value` for which `predicate` returns true,
+/// at any depth, with the result of `transform`. Keys are never changed.
+pub fn tag_strings<P, T>(value: &mut JsonValue, predicate: P, transform: T)
+where
+    P: Fn(&str) -> bool,
+    T: Fn(String) -> JsonValue,
+{
+    tag_strings_with(value, &predicate, &transform);
+}
+
+fn tag_strings_with<P, T>(value: &mut JsonValue, predicate: &P, transform: &T)
+where
+    P: Fn(&str) -> bool,
+    T: Fn(String) -> JsonValue,
+{
+    match value {
+        JsonValue::String(string) if predicate(string) => {
+            *value = transform(std::mem::take(string));
+        }
+        JsonValue::Array(values) => {
+            for value in values {
+                tag_strings_with(value, predicate, transform);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (_, value) in entries {
+                tag_strings_with(value, predicate, transform);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Wraps every string value that is an ISO 8601 date or date-time in an object,
+/// so `"2024-03-01"` becomes `{"$date": "2024-03-01"}`.
+///
+/// Accepted are a calendar date `YYYY-MM-DD`, optionally followed by `T`,
+/// `HH:MM`, optional seconds with an optional fraction, and an optional time
+/// zone of `Z` or `±HH:MM`. Fields are checked for range but not against the
+/// calendar, so `2024-02-31` is tagged.
+pub fn tag_dates(value: &mut JsonValue) {
+    tag_strings(value, is_iso8601_date, |date| {
+        JsonValue::Object(vec![("$date".to_string(), JsonValue::String(date))])
+    });
+}
+
+fn is_iso8601_date(string: &str) -> bool {
+    /// Returns the number after `separator` if it has exactly two digits and is
+    /// within `min..=max`, and the rest of the string.
+    fn field(rest: &str, separator: char, min: u32, max: u32) -> Option<&str> {
+        let rest = rest.strip_prefix(separator)?;
+        let digits = rest.get(..2)?;
+        if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let n: u32 = digits.parse().ok()?;
+        (min..=max).contains(&n).then(|| &rest[2..])
+    }
+
+    let date = (|| {
+        let year = string.get(..4)?;
+        if !year.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let rest = field(&string[4..], '-', 1, 12)?;
+        field(rest, '-', 1, 31)
+    })();
+    let Some(rest) = date else {
+        return false;
+    };
+    if rest.is_empty() {
+        return true;
+    }
+
+    let time = (|| {
+        let rest = field(rest, 'T', 0, 23)?;
+        let mut rest = field(rest, ':', 0, 59)?;
+        if let Some(after_seconds) = field(rest, ':', 0, 60) {
+            rest = after_seconds;
+            if let Some(fraction) = rest.strip_prefix('.') {
+                let digits = fraction.len()
+                    - fraction
+                        .trim_start_matches(|char: char| char.is_ascii_digit())
+                        .len();
+                if digits == 0 {
+                    return None;
+                }
+                rest = &fraction[digits..];
+            }
+        }
+        Some(rest)
+    })();
+    match time {
+        Some("" | "Z") => true,
+        Some(zone) => ['+', '-'].into_iter().any(|sign| {
+            field(zone, sign, 0, 23)
+                .and_then(|rest| field(rest, ':', 0, 59))
+                .is_some_and(str::is_empty)
+        }),
+        None => false,
+    }
+}
+
+/// Replaces every value in `value`, itself included, that is equal to `target`
+/// with a copy of `replacement`. Replacements are not searched again.
+///
+/// With `match_keys`, object keys equal to a string `target` are renamed to a
+/// string `replacement` too, at any depth. Entries keep their position, even if
+/// an object ends up with a repeated key.
+pub fn replace_value(
+    value: &mut JsonValue,
+    target: &JsonValue,
+    replacement: &JsonValue,
+    match_keys: bool,
+) {
+    if value == target {
+        *value = replacement.clone();
+        return;
+    }
+    match value {
+        JsonValue::Array(values) => {
+            for value in values {
+                replace_value(value, target, replacement, match_keys);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (key, value) in entries {
+                if let (true, JsonValue::String(target), JsonValue::String(replacement)) =
+                    (match_keys, target, replacement)
+                {
+                    if key == target {
+                        *key = replacement.clone();
+                    }
+                }
+                replace_value(value, target, replacement, match_keys);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Sorts the elements of an array by the value each has at `key`: numbers in
+/// ascending order, then strings by code point, then elements with any other
+/// value at `key`, then elements that are not objects or don't have `key`.
+///
+/// The sort is stable, so elements that compare equal, including all those in
+/// the last two groups, keep their order. If an object repeats `key`, its last
+/// value is used. Values other than arrays are left unchanged.
+pub fn sort_array_by_key(value: &mut JsonValue, key: &str) {
+    let JsonValue::Array(values) = value else {
+        return;
+    };
+    values.sort_by(|a, b| compare_sort_keys(sort_key(a, key), sort_key(b, key)));
+}
+
+fn sort_key<'a>(value: &'a JsonValue, key: &str) -> Option<&'a JsonValue> {
+    let JsonValue::Object(entries) = value else {
+        return None;
+    };
+    entries
+        .iter()
+        .rev()
+        .find(|(entry_key, _)| entry_key == key)
+        .map(|(_, value)| value)
+}
+
+fn compare_sort_keys(a: Option<&JsonValue>, b: Option<&JsonValue>) -> Ordering {
+    let rank = |value: Option<&JsonValue>| match value {
+        Some(JsonValue::Number(_)) => 0,
+        Some(JsonValue::String(_)) => 1,
+        Some(_) => 2,
+        None => 3,
+    };
+    match (a, b) {
+        (Some(JsonValue::Number(a)), Some(JsonValue::Number(b))) => {
+            a.as_f64().total_cmp(&b.as_f64())
+        }
+        (Some(JsonValue::String(a)), Some(JsonValue::String(b))) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Which string values `coerce_scalars` converts.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Coercions {
+    /// Convert strings such as `"42"` or `"-2.5e3"` to numbers.
+    pub numbers: bool,
+    /// Convert `"true"` and `"false"` to booleans.
+    pub booleans: bool,
+}
+
+/// Converts string values that spell a number or boolean into that type, at any
+/// depth. Keys are never changed.
+///
+/// Only strings that are exactly a JSON literal are converted, with no
+/// surrounding whitespace, so `"007"`, `"+1"`, `".5"`, `"0x1F"` and `"TRUE"` stay
+/// strings. A number is also left as a string if converting it would lose
+/// information: `"1e999"` overflows to infinity, and an integer outside the `i64`
+/// range would be rounded.
+pub fn coerce_scalars(value: &mut JsonValue, coercions: Coercions) {
+    match value {
+        JsonValue::String(string) => {
+            if let Some(coerced) = coerce_scalar(string, coercions) {
+                *value = coerced;
+            }
+        }
+        JsonValue::Array(values) => {
+            for value in values {
+                coerce_scalars(value, coercions);
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (_, value) in entries {
+                coerce_scalars(value, coercions);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn coerce_scalar(string: &str, coercions: Coercions) -> Option<JsonValue> {
+    match string {
+        "true" if coercions.booleans => return Some(JsonValue::Bool(true)),
+        "false" if coercions.booleans => return Some(JsonValue::Bool(false)),
+        _ => {}
+    }
+    if !coercions.numbers || !tokenizer::is_number_literal(string) {
+        return None;
+    }
+
+    let Ok(tokens) = tokenizer::tokenize(string) else {
+        return None;
+    };
+    match tokens.as_slice() {
+        [JsonToken::Number(JsonNumber::Integer(n))] => {
+            Some(JsonValue::Number(JsonNumber::Integer(*n)))
+        }
+        [JsonToken::Number(JsonNumber::Float(n))]
+            if n.is_finite() && string.contains(['.', 'e', 'E']) =>
+        {
+            Some(JsonValue::Number(JsonNumber::Float(*n)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonNumber;
+
+    fn mixed_case_object() -> JsonValue {
+        JsonValue::Object(vec![
+            (
+                "userName".to_string(),
+                JsonValue::String("alice".to_string()),
+            ),
+            (
+                "home_address".to_string(),
+                JsonValue::Object(vec![
+                    ("Street-Name".to_string(), JsonValue::Null),
+                    (
+                        "zipCode".to_string(),
+                        JsonValue::Number(JsonNumber::Integer(12345)),
+                    ),
+                ]),
+            ),
+            (
+                "HTTPServers".to_string(),
+                JsonValue::Array(vec![JsonValue::Object(vec![(
+                    "hostName".to_string(),
+                    JsonValue::String("keep_this-Value".to_string()),
+                )])]),
+            ),
+        ])
+    }
+
+    fn expected(keys: [&str; 6]) -> JsonValue {
+        JsonValue::Object(vec![
+            (keys[0].to_string(), JsonValue::String("alice".to_string())),
+            (
+                keys[1].to_string(),
+                JsonValue::Object(vec![
+                    (keys[2].to_string(), JsonValue::Null),
+                    (
+                        keys[3].to_string(),
+                        JsonValue::Number(JsonNumber::Integer(12345)),
+                    ),
+                ]),
+            ),
+            (
+                keys[4].to_string(),
+                JsonValue::Array(vec![JsonValue::Object(vec![(
+                    keys[5].to_string(),
+                    JsonValue::String("keep_this-Value".to_string()),
+                )])]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn rename_keys_snake_case() {
+        let mut value = mixed_case_object();
+        rename_keys(&mut value, KeyCase::SnakeCase);
+        assert_eq!(
+            value,
+            expected([
+                "user_name",
+                "home_address",
+                "street_name",
+                "zip_code",
+                "http_servers",
+                "host_name"
+            ])
+        );
+    }
+
+    #[test]
+    fn rename_keys_camel_case() {
+        let mut value = mixed_case_object();
+        rename_keys(&mut value, KeyCase::CamelCase);
+        assert_eq!(
+            value,
+            expected([
+                "userName",
+                "homeAddress",
+                "streetName",
+                "zipCode",
+                "httpServers",
+                "hostName"
+            ])
+        );
+    }
+
+    #[test]
+    fn rename_keys_kebab_case() {
+        let mut value = mixed_case_object();
+        rename_keys(&mut value, KeyCase::KebabCase);
+        assert_eq!(
+            value,
+            expected([
+                "user-name",
+                "home-address",
+                "street-name",
+                "zip-code",
+                "http-servers",
+                "host-name"
+            ])
+        );
+    }
+
+    #[test]
+    fn rename_keys_collision_last_wins() {
+        let mut value = JsonValue::Object(vec![
+            (
+                "user_id".to_string(),
+                JsonValue::Number(JsonNumber::Integer(1)),
+            ),
+            ("other".to_string(), JsonValue::Null),
+            (
+                "userId".to_string(),
+                JsonValue::Number(JsonNumber::Integer(2)),
+            ),
+        ]);
+        rename_keys(&mut value, KeyCase::SnakeCase);
+        assert_eq!(
+            value,
+            JsonValue::Object(vec![
+                (
+                    "user_id".to_string(),
+                    JsonValue::Number(JsonNumber::Integer(2))
+                ),
+                ("other".to_string(), JsonValue::Null),
+            ])
+        );
+    }
+
+    #[test]
+    fn coerce_scalars_converts_literals() {
+        let mut value =
+            crate::parse(r#"{"count": "42", "active": "true", "ratio": ["-2.5e3", "0", "false"]}"#)
+                .unwrap();
+        let all = Coercions {
+            numbers: true,
+            booleans: true,
+        };
+        coerce_scalars(&mut value, all);
+        assert_eq!(
+            value,
+            crate::parse(r#"{"count": 42, "active": true, "ratio": [-2500.0, 0, false]}"#).unwrap()
+        );
+
+        let mut value = crate::parse(r#"["1", "true"]"#).unwrap();
+        coerce_scalars(
+            &mut value,
+            Coercions {
+                booleans: true,
+                ..Coercions::default()
+            },
+        );
+        assert_eq!(value, crate::parse(r#"["1", true]"#).unwrap());
+    }
+
+    #[test]
+    fn coerce_scalars_keeps_other_strings() {
+        let content = r#"{"42": ["007", "+1", ".5", "1.", "1e", "-", "0x1F", " 1", "1 ", "1_000",
+            "TRUE", "yes", "", "1e999", "9223372036854775808", "NaN", "null"]}"#;
+        let mut value = crate::parse(content).unwrap();
+        coerce_scalars(
+            &mut value,
+            Coercions {
+                numbers: true,
+                booleans: true,
+            },
+        );
+        assert_eq!(value, crate::parse(content).unwrap());
+    }
+
+    #[test]
+    fn tag_strings_with_closure() {
+        let mut value =
+            crate::parse(r#"{"id": "@12", "tags": ["@a", "b", {"@c": "@d"}], "n": 1}"#).unwrap();
+        tag_strings(
+            &mut value,
+            |string| string.starts_with('@'),
+            |string| JsonValue::String(string[1..].to_uppercase()),
+        );
+        assert_eq!(
+            value,
+            crate::parse(r#"{"id": "12", "tags": ["A", "b", {"@c": "D"}], "n": 1}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn tag_dates_iso8601() {
+        let dates = [
+            "2024-03-01",
+            "2024-03-01T12:30",
+            "2024-03-01T12:30:59Z",
+            "2024-03-01T23:59:60.123+05:30",
+            "1999-12-31T00:00:00-08:00",
+        ];
+        for date in dates {
+            let mut value = JsonValue::Array(vec![JsonValue::String(date.to_string())]);
+            tag_dates(&mut value);
+            assert_eq!(
+                value,
+                JsonValue::Array(vec![JsonValue::Object(vec![(
+                    "$date".to_string(),
+                    JsonValue::String(date.to_string())
+                )])]),
+                "{:?} was not tagged",
+                date
+            );
+        }
+
+        let others = [
+            "2024",
+            "2024-3-01",
+            "2024-13-01",
+            "2024-03-00",
+            "2024-03-01 12:30",
+            "2024-03-01T24:00",
+            "2024-03-01T12:30:00.",
+            "2024-03-01T12:30+0530",
+            "2024-03-01Z",
+            "20240301",
+            "2024-03-01T12:30:00Zjunk",
+            "not a date",
+        ];
+        let content = format!("{:?}", others);
+        let mut value = crate::parse(&content).unwrap();
+        tag_dates(&mut value);
+        assert_eq!(value, crate::parse(&content).unwrap());
+    }
+
+    #[test]
+    fn sort_array_by_numeric_key() {
+        let mut value =
+            crate::parse(r#"[{"id": 3, "n": "c"}, {"id": -1.5, "n": "a"}, {"id": 2, "n": "b"}]"#)
+                .unwrap();
+        sort_array_by_key(&mut value, "id");
+        assert_eq!(
+            value,
+            crate::parse(r#"[{"id": -1.5, "n": "a"}, {"id": 2, "n": "b"}, {"id": 3, "n": "c"}]"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn sort_array_by_string_key() {
+        let mut value = crate::parse(
+            r#"[{"n": "pear"}, {"n": "Apple"}, {"n": "apple"}, {"n": "pear", "x": 1}]"#,
+        )
+        .unwrap();
+        sort_array_by_key(&mut value, "n");
+        assert_eq!(
+            value,
+            crate::parse(
+                r#"[{"n": "Apple"}, {"n": "apple"}, {"n": "pear"}, {"n": "pear", "x": 1}]"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn sort_array_by_key_missing_last() {
+        let mut value = crate::parse(
+            r#"[{"k": null}, 7, {"other": 1}, {"k": "b"}, {"k": 2}, {"k": "a", "k": 1}]"#,
+        )
+        .unwrap();
+        sort_array_by_key(&mut value, "k");
+        assert_eq!(
+            value,
+            crate::parse(
+                r#"[{"k": "a", "k": 1}, {"k": 2}, {"k": "b"}, {"k": null}, 7, {"other": 1}]"#
+            )
+            .unwrap()
+        );
+
+        let mut object = crate::parse(r#"{"k": [2, 1]}"#).unwrap();
+        sort_array_by_key(&mut object, "k");
+        assert_eq!(object, crate::parse(r#"{"k": [2, 1]}"#).unwrap());
+    }
+
+    #[test]
+    fn replace_value_redacts_nested_strings() {
+        let mut value = crate::parse(
+            r#"{"token": "s3cret", "s3cret": ["s3cret", {"nested": "s3cret"}, "s3cret!"]}"#,
+        )
+        .unwrap();
+        let secret = JsonValue::String("s3cret".to_string());
+        let redacted = JsonValue::String("***".to_string());
+
+        let mut keys_kept = value.clone();
+        replace_value(&mut keys_kept, &secret, &redacted, false);
+        assert_eq!(
+            keys_kept,
+            crate::parse(r#"{"token": "***", "s3cret": ["***", {"nested": "***"}, "s3cret!"]}"#)
+                .unwrap()
+        );
+
+        replace_value(&mut value, &secret, &redacted, true);
+        assert_eq!(
+            value,
+            crate::parse(r#"{"token": "***", "***": ["***", {"nested": "***"}, "s3cret!"]}"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn replace_value_keys_need_a_string_replacement() {
+        let mut value = crate::parse(r#"{"a": "a"}"#).unwrap();
+        let target = JsonValue::String("a".to_string());
+        replace_value(&mut value, &target, &crate::parse("1").unwrap(), true);
+        assert_eq!(value, crate::parse(r#"{"a": 1}"#).unwrap());
+    }
+
+    #[test]
+    fn replace_value_containers() {
+        let mut value = crate::parse(r#"[[1], [[1]], {"a": [1]}]"#).unwrap();
+        let target = crate::parse("[1]").unwrap();
+        // The replacement contains the target, but isn't searched again.
+        let replacement = crate::parse("[[1]]").unwrap();
+        replace_value(&mut value, &target, &replacement, true);
+        assert_eq!(
+            value,
+            crate::parse(r#"[[[1]], [[[1]]], {"a": [[1]]}]"#).unwrap()
+        );
+    }
+}
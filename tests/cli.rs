@@ -0,0 +1,512 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::{env, fs, process};
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("json-formatter-{}-{}", process::id(), name))
+}
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_json-formatter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The binary may exit on a bad option before reading its input.
+    if let Err(error) = child.stdin.take().unwrap().write_all(stdin.as_bytes()) {
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn output_flag_writes_formatted_file() {
+    let input = temp_path("output_flag_input.json");
+    let output = temp_path("output_flag_output.json");
+    fs::write(&input, r#"{"a":[1,true]}"#).unwrap();
+
+    let result = run(
+        &["-o", output.to_str().unwrap(), input.to_str().unwrap()],
+        "",
+    );
+
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "{\n  \"a\": [\n    1,\n    true\n  ]\n}\n"
+    );
+    fs::remove_file(input).unwrap();
+    fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn output_flag_reads_stdin_without_input_file() {
+    let output = temp_path("output_flag_stdin.json");
+
+    let result = run(&["--output", output.to_str().unwrap()], "[null]");
+
+    assert!(result.status.success());
+    assert_eq!(fs::read_to_string(&output).unwrap(), "[\n  null\n]\n");
+    fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn output_flag_is_not_written_on_error() {
+    let output = temp_path("output_flag_error.json");
+
+    let result = run(&["-o", output.to_str().unwrap()], "[1,");
+
+    assert!(!result.status.success());
+    assert!(!output.exists());
+}
+
+#[test]
+fn tab_width_affects_inline_array_layout() {
+    let input = r#"{"key": [1, 2]}"#;
+    let args = ["--indent", "tab", "--inline-array-width", "20"];
+
+    let result = run(&[&args[..], &["--tab-width", "4"]].concat(), input);
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "{\n\t\"key\": [1, 2]\n}\n"
+    );
+
+    let result = run(&[&args[..], &["--tab-width", "8"]].concat(), input);
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "{\n\t\"key\": [\n\t\t1,\n\t\t2\n\t]\n}\n"
+    );
+}
+
+#[test]
+fn invalid_option_value_is_rejected() {
+    let result = run(&["--tab-width", "wide"], "{}");
+    assert!(!result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Invalid value for option '--tab-width': 'wide'\n"
+    );
+}
+
+#[test]
+fn invalid_json_reports_error_without_panicking() {
+    let result = run(&[], r#"{"a": tru}"#);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Unexpected literal: 'tru'\n"
+    );
+
+    let result = run(&[], "[1,");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Unexpected end of input\n"
+    );
+}
+
+#[test]
+fn no_duplicate_keys_flag_rejects_duplicates() {
+    let input = temp_path("duplicate_keys.json");
+    fs::write(&input, r#"{"name": "a", "port": 80, "name": "b"}"#).unwrap();
+    let path = input.to_str().unwrap();
+
+    let result = run(&[path], "");
+    assert!(result.status.success());
+
+    let result = run(&["--no-duplicate-keys", path], "");
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        format!("Duplicate key: 'name' in '{}'\n", path)
+    );
+    fs::remove_file(input).unwrap();
+}
+
+#[test]
+fn raw_flag_prints_strings_unquoted() {
+    let result = run(&["--raw"], r#""Alice \"A\"\tSmith""#);
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "Alice \"A\"\tSmith\n"
+    );
+
+    let result = run(&["--raw"], "42");
+    assert!(result.status.success());
+    assert_eq!(String::from_utf8(result.stdout).unwrap(), "42\n");
+}
+
+#[test]
+fn bom_flag_prefixes_output() {
+    let result = run(&["--bom"], "[1]");
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "\u{FEFF}[\n  1\n]\n"
+    );
+}
+
+#[test]
+fn bom_flag_output_formats_again_in_place() {
+    let path = temp_path("bom_in_place.json");
+    fs::write(&path, "[1]").unwrap();
+    let file = path.to_str().unwrap();
+
+    for _ in 0..2 {
+        let result = run(&["-w", "--bom", file], "");
+        assert!(result.status.success());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "\u{FEFF}[\n  1\n]\n");
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn array_indent_flag() {
+    let result = run(&["--indent", "2", "--array-indent", "4"], r#"{"a": [1]}"#);
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "{\n  \"a\": [\n      1\n  ]\n}\n"
+    );
+}
+
+#[test]
+fn format_command_matches_default() {
+    let input = r#"{"a":[1,2]}"#;
+    let result = run(&["format"], input);
+    assert!(result.status.success());
+    assert_eq!(result.stdout, run(&[], input).stdout);
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n"
+    );
+}
+
+#[test]
+fn minify_command() {
+    let result = run(&["minify"], "{\n  \"a\": [1, 2],\n  \"b\": {}\n}\n");
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "{\"a\":[1,2],\"b\":{}}\n"
+    );
+
+    let result = run(&["minify", "--indent", "4"], "{}");
+    assert!(!result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Unknown option: '--indent'\n"
+    );
+}
+
+#[test]
+fn check_command() {
+    let result = run(&["check"], "{\n  \"a\": 1\n}\n");
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+
+    let result = run(&["check", "--indent", "4"], "{\n  \"a\": 1\n}\n");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "'<stdin>' is not formatted\n"
+    );
+}
+
+#[test]
+fn check_command_reports_mixed_indentation() {
+    let result = run(&["check"], "{\n  \"a\": [\n\t\t1,\n  \t2\n  ]\n}\n");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "'<stdin>' mixes tabs and spaces in the indentation of lines 3, 4\n"
+    );
+}
+
+#[test]
+fn check_command_accepts_tab_indent_with_space_array_indent() {
+    let layout = ["--indent", "tab", "--array-indent", "2"];
+    let input = r#"{"a": {"b": [1, [2]]}, "c": [{"d": null}]}"#;
+    let formatted = run(&layout, input);
+    assert!(formatted.status.success());
+    let formatted = String::from_utf8(formatted.stdout).unwrap();
+    assert!(formatted.contains("\t  ") && formatted.contains("\n\t\t"));
+
+    let result = run(&[&["check"][..], &layout].concat(), &formatted);
+    assert!(result.status.success());
+    assert!(result.stderr.is_empty());
+
+    // Only the line indented differently from the formatter's output is reported.
+    let unformatted = formatted.replacen("\t\t  1", "\t\t 1", 1);
+    let result = run(&[&["check"][..], &layout].concat(), &unformatted);
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "'<stdin>' mixes tabs and spaces in the indentation of line 4\n"
+    );
+}
+
+#[test]
+fn validate_command() {
+    let result = run(&["validate"], "{\"a\": 1}");
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+
+    let result = run(&["validate"], "{\"a\": }");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Expected a value, found '}' at line 1, column 7\n"
+    );
+}
+
+#[test]
+fn validate_flag_is_silent_on_valid_files() {
+    let valid = temp_path("validate_flag_valid.json");
+    let other = temp_path("validate_flag_other.json");
+    fs::write(&valid, r#"{"a": [1, true]}"#).unwrap();
+    fs::write(&other, "null").unwrap();
+
+    let result = run(&["--validate", valid.to_str().unwrap()], "");
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+    assert!(result.stderr.is_empty());
+
+    let result = run(
+        &[
+            "--validate",
+            valid.to_str().unwrap(),
+            other.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+    assert!(result.stderr.is_empty());
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(other).unwrap();
+}
+
+#[test]
+fn validate_flag_reports_invalid_files() {
+    let valid = temp_path("validate_flag_ok.json");
+    let invalid = temp_path("validate_flag_invalid.json");
+    fs::write(&valid, "[1]").unwrap();
+    fs::write(&invalid, r#"{"a": }"#).unwrap();
+
+    let result = run(&["--validate", invalid.to_str().unwrap()], "");
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Expected a value, found '}' at line 1, column 7\n"
+    );
+
+    let result = run(
+        &[
+            "--validate",
+            invalid.to_str().unwrap(),
+            valid.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        format!(
+            "Expected a value, found '}}' at line 1, column 7 in '{}'\n",
+            invalid.display()
+        )
+    );
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(invalid).unwrap();
+}
+
+#[test]
+fn validate_flag_can_follow_the_files() {
+    let valid = temp_path("validate_flag_after_valid.json");
+    let invalid = temp_path("validate_flag_after_invalid.json");
+    fs::write(&valid, "[1]").unwrap();
+    fs::write(&invalid, "{\n  \"a\": 1\n  \"b\": 2\n}").unwrap();
+    let files = [valid.to_str().unwrap(), invalid.to_str().unwrap()];
+
+    let result = run(&[files[0], "--validate"], "");
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+
+    let result = run(&[files[0], files[1], "--validate"], "");
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        format!(
+            "Unexpected token: '\"b\"' at line 3, column 3 in '{}'\n",
+            files[1]
+        )
+    );
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(invalid).unwrap();
+}
+
+#[test]
+fn validate_rejects_output_options() {
+    let result = run(&["--indent", "4", "--validate"], "{}");
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Option '--indent' can't be used with '--validate'\n"
+    );
+
+    let result = run(&["validate", "-o", "out.json"], "{}");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Option '-o' can't be used with 'validate'\n"
+    );
+}
+
+#[test]
+fn combine_flag_collects_files_into_array() {
+    let first = temp_path("combine_first.json");
+    let second = temp_path("combine_second.json");
+    fs::write(&first, r#"{"id": 1}"#).unwrap();
+    fs::write(&second, r#"{"id": 2}"#).unwrap();
+
+    let result = run(
+        &[
+            "minify",
+            "--combine",
+            first.to_str().unwrap(),
+            second.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        "[{\"id\":1},{\"id\":2}]\n"
+    );
+
+    let result = run(&[first.to_str().unwrap(), second.to_str().unwrap()], "");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        format!("Unexpected argument: '{}'\n", second.display())
+    );
+    fs::remove_file(first).unwrap();
+    fs::remove_file(second).unwrap();
+}
+
+#[test]
+fn flatten_flag_spreads_arrays() {
+    let first = temp_path("flatten_first.json");
+    let second = temp_path("flatten_second.json");
+    fs::write(&first, "[1, [2]]").unwrap();
+    fs::write(&second, "3").unwrap();
+    let files = [first.to_str().unwrap(), second.to_str().unwrap()];
+
+    let result = run(&["minify", "--combine", files[0], files[1]], "");
+    assert_eq!(String::from_utf8(result.stdout).unwrap(), "[[1,[2]],3]\n");
+
+    let result = run(
+        &["minify", "--combine", "--flatten", files[0], files[1]],
+        "",
+    );
+    assert!(result.status.success());
+    assert_eq!(String::from_utf8(result.stdout).unwrap(), "[1,[2],3]\n");
+    fs::remove_file(first).unwrap();
+    fs::remove_file(second).unwrap();
+}
+
+#[test]
+fn write_flag_formats_files_in_place() {
+    let first = temp_path("write_first.json");
+    let second = temp_path("write_second.json");
+    fs::write(&first, r#"{"a":[1]}"#).unwrap();
+    fs::write(&second, "[\n  null\n]\n").unwrap();
+    let files = [first.to_str().unwrap(), second.to_str().unwrap()];
+
+    let result = run(&["-w", files[0], files[1]], "");
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+    assert_eq!(
+        fs::read_to_string(&first).unwrap(),
+        "{\n  \"a\": [\n    1\n  ]\n}\n"
+    );
+    assert_eq!(fs::read_to_string(&second).unwrap(), "[\n  null\n]\n");
+
+    let result = run(&["-w", "-o", files[1], files[0]], "");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Options '-w' and '-o' can't be used together\n"
+    );
+    fs::remove_file(first).unwrap();
+    fs::remove_file(second).unwrap();
+}
+
+#[test]
+fn dry_run_lists_files_that_would_change() {
+    let unformatted = temp_path("dry_run_unformatted.json");
+    let formatted = temp_path("dry_run_formatted.json");
+    fs::write(&unformatted, r#"{"a": 1}"#).unwrap();
+    fs::write(&formatted, "{\n  \"a\": 1\n}\n").unwrap();
+    let files = [unformatted.to_str().unwrap(), formatted.to_str().unwrap()];
+
+    let result = run(&["-w", "--dry-run", files[0], files[1]], "");
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8(result.stdout).unwrap(),
+        format!("{}\n", files[0])
+    );
+    assert_eq!(fs::read_to_string(&unformatted).unwrap(), r#"{"a": 1}"#);
+    assert_eq!(
+        fs::read_to_string(&formatted).unwrap(),
+        "{\n  \"a\": 1\n}\n"
+    );
+
+    let result = run(&["--dry-run", files[0]], "");
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(result.stderr).unwrap(),
+        "Option '--dry-run' requires '-w'\n"
+    );
+    fs::remove_file(unformatted).unwrap();
+    fs::remove_file(formatted).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap_flag_formats_large_file() {
+    let input = temp_path("mmap_large.json");
+    let records: Vec<String> = (0..100_000)
+        .map(|index| {
+            format!(
+                r#"{{"id":{},"name":"item é {}","tags":["a","b"]}}"#,
+                index, index
+            )
+        })
+        .collect();
+    fs::write(&input, format!("[{}]", records.join(","))).unwrap();
+
+    // Only the output is compared. Peak memory isn't measured: mapping saves no
+    // more than the copy of the input into a string.
+    let mapped = run(&["--mmap", input.to_str().unwrap()], "");
+    let read = run(&[input.to_str().unwrap()], "");
+
+    assert!(mapped.status.success());
+    assert!(mapped.stdout.starts_with(b"[\n  {\n    \"id\": 0,\n"));
+    assert_eq!(mapped.stdout, read.stdout);
+    fs::remove_file(input).unwrap();
+}